@@ -0,0 +1,297 @@
+//! Benchmarks the sorting core outside the bevy app.
+//!
+//! `cargo bench --bench sorting -- [filter] [--save-baseline NAME] [--baseline NAME]`,
+//! add `--no-default-features` to skip building bevy
+//!
+//! * `filter` only runs benchmarks whose `algorithm/pattern/size` id contains it
+//! * `--save-baseline NAME` writes the results to target/bench-baselines/NAME.csv
+//! * `--baseline NAME` compares the results with a saved baseline
+
+use iteration2::algorithms::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{BufRead, BufReader, Write};
+use std::time::{Duration, Instant};
+
+const SIZES: [usize; 4] = [100, 1_000, 10_000, 100_000];
+const WARMUP: Duration = Duration::from_millis(200);
+const MEASURE: Duration = Duration::from_secs(1);
+const MIN_SAMPLES: usize = 10;
+const MAX_SAMPLES: usize = 200;
+const BASELINE_DIR: &str = "target/bench-baselines";
+
+#[derive(Clone, Copy)]
+enum Pattern {
+    Random,
+    Sorted,
+    Reversed,
+    /// sorted with 1% of the elements swapped
+    NearlySorted,
+    /// only 8 distinct keys
+    FewUnique,
+}
+
+impl Pattern {
+    const ALL: [Pattern; 5] = [
+        Pattern::Random,
+        Pattern::Sorted,
+        Pattern::Reversed,
+        Pattern::NearlySorted,
+        Pattern::FewUnique,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Pattern::Random => "random",
+            Pattern::Sorted => "sorted",
+            Pattern::Reversed => "reversed",
+            Pattern::NearlySorted => "nearly_sorted",
+            Pattern::FewUnique => "few_unique",
+        }
+    }
+
+    fn keys(&self, length: usize) -> Vec<f32> {
+        let mut rng = XorShift(0x9E37_79B9 ^ length as u32);
+        match self {
+            Pattern::Random => (0..length).map(|_| rng.next_f32() * 1000.).collect(),
+            Pattern::Sorted => (0..length).map(|i| i as f32).collect(),
+            Pattern::Reversed => (0..length).rev().map(|i| i as f32).collect(),
+            Pattern::NearlySorted => {
+                let mut keys: Vec<f32> = (0..length).map(|i| i as f32).collect();
+                for _ in 0..length / 100 {
+                    let a = rng.next_index(length);
+                    let b = rng.next_index(length);
+                    keys.swap(a, b);
+                }
+                keys
+            }
+            Pattern::FewUnique => (0..length).map(|_| rng.next_index(8) as f32).collect(),
+        }
+    }
+}
+
+struct XorShift(u32);
+
+impl XorShift {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.next() as f32 / u32::MAX as f32
+    }
+
+    fn next_index(&mut self, length: usize) -> usize {
+        self.next() as usize % length.max(1)
+    }
+}
+
+/// An algorithm prepares its input outside the timed region and returns the timed sort.
+type Prepare = fn(&[f32]) -> Box<dyn FnMut()>;
+
+fn ball_data(keys: &[f32]) -> Vec<BallData<u64>> {
+    keys.iter()
+        .enumerate()
+        .map(|(entity, distance)| BallData { entity: entity as u64, distance: *distance })
+        .collect()
+}
+
+fn algorithms() -> Vec<(&'static str, Prepare)> {
+    vec![
+        ("MemoryEfficient", |keys| {
+            let mut balls = ball_data(keys);
+            let mut temp = balls.clone();
+            Box::new(move || merge_sort(black_box(&mut balls), &mut temp, SortOrder::Ascending))
+        }),
+        ("TopDown", |keys| {
+            // like the temp buffers of the others, the queues are allocated before timing
+            let mut pool = QueuePool::default();
+            pool.warm(keys.len(), BallData { entity: 0, distance: 0. });
+            let mut unsorted = pool.take(keys.len());
+            unsorted.extend(ball_data(keys));
+            let mut balls = Some(unsorted);
+            Box::new(move || {
+                let sorted = merge_top(balls.take().unwrap(), SortOrder::Ascending, &mut pool);
+                black_box(&sorted);
+                pool.give(sorted);
+            })
+        }),
+        ("BottomUp", |keys| {
+            let mut balls = ball_data(keys);
+            let mut temp = balls.clone();
+            Box::new(move || merge_bottom(black_box(&mut balls), &mut temp, SortOrder::Ascending))
+        }),
+        ("MemoryEfficientSoA", |keys| {
+            let mut keys = keys.to_vec();
+            let mut indices: Vec<u32> = (0..keys.len() as u32).collect();
+            let (mut temp_keys, mut temp_indices) = (keys.clone(), indices.clone());
+            Box::new(move || {
+                merge_sort_soa(
+                    black_box(&mut keys),
+                    &mut indices,
+                    &mut temp_keys,
+                    &mut temp_indices,
+                    SortOrder::Ascending,
+                )
+            })
+        }),
+        ("BottomUpSoA", |keys| {
+            let mut keys = keys.to_vec();
+            let mut indices: Vec<u32> = (0..keys.len() as u32).collect();
+            let (mut temp_keys, mut temp_indices) = (keys.clone(), indices.clone());
+            Box::new(move || {
+                merge_bottom_soa(
+                    black_box(&mut keys),
+                    &mut indices,
+                    &mut temp_keys,
+                    &mut temp_indices,
+                    SortOrder::Ascending,
+                )
+            })
+        }),
+        // std's stable sort as a reference point
+        ("StdStable", |keys| {
+            let mut balls = ball_data(keys);
+            Box::new(move || black_box(&mut balls).sort_by(|a, b| a.distance.total_cmp(&b.distance)))
+        }),
+    ]
+}
+
+struct Summary {
+    samples: usize,
+    min: f64,
+    median: f64,
+    mean: f64,
+    stddev: f64,
+}
+
+impl Summary {
+    fn new(mut samples: Vec<f64>) -> Summary {
+        samples.sort_by(f64::total_cmp);
+        let count = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / count;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1.).max(1.);
+        let middle = samples.len() / 2;
+        let median = if samples.len().is_multiple_of(2) {
+            (samples[middle - 1] + samples[middle]) / 2.
+        } else {
+            samples[middle]
+        };
+
+        Summary {
+            samples: samples.len(),
+            min: samples[0],
+            median,
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+/// runs one sort per sample, every sample starts from the unsorted keys
+fn measure(prepare: Prepare, keys: &[f32]) -> Summary {
+    let warmup_start = Instant::now();
+    while warmup_start.elapsed() < WARMUP {
+        let mut sort = prepare(keys);
+        sort();
+    }
+
+    let mut samples = Vec::with_capacity(MAX_SAMPLES);
+    let measure_start = Instant::now();
+    while samples.len() < MAX_SAMPLES
+        && (samples.len() < MIN_SAMPLES || measure_start.elapsed() < MEASURE)
+    {
+        let mut sort = prepare(keys);
+        let start = Instant::now();
+        sort();
+        samples.push(start.elapsed().as_nanos() as f64);
+    }
+
+    Summary::new(samples)
+}
+
+fn read_baseline(name: &str) -> HashMap<String, (f64, f64)> {
+    let path = format!("{BASELINE_DIR}/{name}.csv");
+    let file = File::open(&path).unwrap_or_else(|e| panic!("could not open baseline {path}: {e}"));
+
+    let mut baseline = HashMap::new();
+    for line in BufReader::new(file).lines().skip(1) {
+        let line = line.unwrap();
+        let cells: Vec<&str> = line.split(',').collect();
+        // id,samples,min,median,mean,stddev
+        if let [id, _, _, median, _, stddev] = cells[..] {
+            baseline.insert(id.to_string(), (median.parse().unwrap(), stddev.parse().unwrap()));
+        }
+    }
+    baseline
+}
+
+fn main() {
+    let mut filter = None;
+    let mut save_baseline = None;
+    let mut compare_baseline = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--save-baseline" => save_baseline = args.next(),
+            "--baseline" => compare_baseline = args.next(),
+            // passed along by cargo bench
+            "--bench" => {}
+            _ if arg.starts_with("--") => {}
+            _ => filter = Some(arg),
+        }
+    }
+
+    let baseline = compare_baseline.as_deref().map(read_baseline);
+    let mut results: Vec<(String, Summary)> = Vec::new();
+
+    for (algorithm, prepare) in algorithms() {
+        for pattern in Pattern::ALL {
+            for size in SIZES {
+                let id = format!("{algorithm}/{}/{size}", pattern.name());
+                if filter.as_ref().is_some_and(|f| !id.contains(f.as_str())) {
+                    continue;
+                }
+
+                let summary = measure(prepare, &pattern.keys(size));
+                print!(
+                    "{id:<40} median {:>12.0} ns  mean {:>12.0} ns  ± {:>10.0}  min {:>12.0}  ({} samples)",
+                    summary.median, summary.mean, summary.stddev, summary.min, summary.samples
+                );
+
+                if let Some((base_median, base_stddev)) = baseline.as_ref().and_then(|b| b.get(&id)) {
+                    let change = (summary.median - base_median) / base_median * 100.;
+                    // changes within the combined spread are reported as noise
+                    let noise = (summary.stddev + base_stddev) / base_median * 100.;
+                    let verdict = if change.abs() <= noise {
+                        "no change"
+                    } else if change > 0. {
+                        "regressed"
+                    } else {
+                        "improved"
+                    };
+                    print!("  {change:+.1}% {verdict}");
+                }
+                println!();
+
+                results.push((id, summary));
+            }
+        }
+    }
+
+    if let Some(name) = save_baseline {
+        std::fs::create_dir_all(BASELINE_DIR).unwrap();
+        let path = format!("{BASELINE_DIR}/{name}.csv");
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "id,samples,min,median,mean,stddev").unwrap();
+        for (id, s) in results {
+            writeln!(file, "{id},{},{},{},{},{}", s.samples, s.min, s.median, s.mean, s.stddev).unwrap();
+        }
+        println!("Saved baseline to {path}");
+    }
+}
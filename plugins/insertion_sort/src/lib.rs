@@ -0,0 +1,54 @@
+//! Example sort plugin, build with `cargo build --release` and run the app with
+//! `--sort-library plugins/insertion_sort/target/release/libinsertion_sort.so`
+
+use std::ffi::c_char;
+
+// must match `SORT_PLUGIN_ABI_VERSION`, `SortPair` and `SortPluginDescriptor` in src/dynamic.rs
+const SORT_PLUGIN_ABI_VERSION: u32 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SortPair {
+    pub entity: u64,
+    pub key: f32,
+}
+
+#[repr(C)]
+pub struct SortPluginDescriptor {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    pub sort: unsafe extern "C" fn(pairs: *mut SortPair, len: usize, descending: bool),
+}
+
+/// # Safety
+/// `pairs` must point to `len` initialized pairs
+unsafe extern "C" fn insertion_sort(pairs: *mut SortPair, len: usize, descending: bool) {
+    if len == 0 {
+        return;
+    }
+    // SAFETY: guaranteed by the caller
+    let pairs = unsafe { std::slice::from_raw_parts_mut(pairs, len) };
+
+    for i in 1..pairs.len() {
+        let current = pairs[i];
+        let mut j = i;
+        while j > 0 && out_of_order(pairs[j - 1].key, current.key, descending) {
+            pairs[j] = pairs[j - 1];
+            j -= 1;
+        }
+        pairs[j] = current;
+    }
+}
+
+fn out_of_order(first: f32, second: f32, descending: bool) -> bool {
+    if descending { first < second } else { first > second }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sort_plugin_descriptor() -> SortPluginDescriptor {
+    SortPluginDescriptor {
+        abi_version: SORT_PLUGIN_ABI_VERSION,
+        name: c"InsertionSort".as_ptr(),
+        sort: insertion_sort,
+    }
+}
@@ -1,82 +1,136 @@
-use crate::MarkBallMessage;
-use crate::ball::{Ball, Special};
-use crate::experiment::ExperimentParameters;
-use bevy::prelude::*;
-use std::cmp::min;
-use std::collections::VecDeque;
-use std::time::Instant;
-use bevy::render::render_resource::encase::private::RuntimeSizedArray;
-
-struct BallData {
-    entity: Entity,
-    distance: f32,
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::min;
+
+#[cfg_attr(feature = "bevy", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    /// nearest ball first
+    Ascending,
+    /// farthest ball first
+    Descending,
 }
 
-impl Clone for BallData {
-    fn clone(&self) -> Self {
-        BallData {
-            entity: self.entity,
-            distance: self.distance,
+impl SortOrder {
+    pub fn name(&self) -> String {
+        match self {
+            SortOrder::Ascending => "ascending".to_string(),
+            SortOrder::Descending => "descending".to_string(),
+        }
+    }
+
+    /// true if `first` may stay in front of `second`, equal keys keep their order
+    #[inline]
+    pub fn in_order(self, first: f32, second: f32) -> bool {
+        match self {
+            SortOrder::Ascending => first <= second,
+            SortOrder::Descending => first >= second,
         }
     }
 }
 
-impl Copy for BallData {}
+/// Array of structs layout, each merge moves the entity along with its distance
+#[derive(Clone, Copy)]
+pub struct BallData<E> {
+    pub entity: E,
+    /// the sort key, the distance unless another key is selected
+    pub distance: f32,
+}
 
-fn allocate_vec_with_placeholders(length: usize) -> Vec<BallData> {
-    vec![
-        BallData {
-            entity: Entity::PLACEHOLDER,
-            distance: 0.
-        };
-        length
-    ]
+/// Queues handed back by `merge_top`, reused by the next recursion instead of allocating
+pub struct QueuePool<T> {
+    /// indexed by the number of bits of the capacity, so a queue is only taken
+    /// for lengths close to the ones it grew for
+    queues: Vec<Vec<VecDeque<T>>>,
 }
 
-pub fn top_down(
-    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
-    special: Single<&Transform, With<Special>>,
-    exp_params: &Res<ExperimentParameters>,
-    mut writer: MessageWriter<MarkBallMessage>,
-) -> u128 {
-    // https://en.wikipedia.org/wiki/Merge_sort
-
-    let mut unsorted_ball_list: VecDeque<BallData> =
-        VecDeque::with_capacity(exp_params.current_sample_size());
-
-    for ball in balls {
-        unsorted_ball_list.push_back(BallData {
-            entity: ball.0,
-            distance: ball.1.translation.distance_squared(special.translation),
-        })
+impl<T> Default for QueuePool<T> {
+    fn default() -> Self {
+        QueuePool { queues: Vec::new() }
     }
+}
 
-    let start = Instant::now();
-    let sorted_balls = merge_top(unsorted_ball_list);
-    let elapsed = start.elapsed().as_nanos();
+fn size_class(capacity: usize) -> usize {
+    (usize::BITS - capacity.leading_zeros()) as usize
+}
 
-    for i in 0..exp_params.pick_number {
-        writer.write(MarkBallMessage(sorted_balls[i].entity));
+impl<T> QueuePool<T> {
+    /// a queue of the size class of `capacity`, taking a larger one would leave
+    /// its class empty and make the pool allocate another large queue
+    pub fn take(&mut self, capacity: usize) -> VecDeque<T> {
+        let mut queue = self
+            .queues
+            .get_mut(size_class(capacity))
+            .and_then(Vec::pop)
+            .unwrap_or_default();
+        queue.reserve_exact(capacity);
+        queue
     }
 
-    elapsed
+    pub fn give(&mut self, mut queue: VecDeque<T>) {
+        queue.clear();
+        let class = size_class(queue.capacity());
+        if self.queues.len() <= class {
+            self.queues.resize_with(class + 1, Vec::new);
+        }
+        self.queues[class].push(queue);
+    }
+
+    /// number of elements every queue in the pool has room for
+    pub fn capacity(&self) -> usize {
+        self.queues.iter().flatten().map(VecDeque::capacity).sum()
+    }
 }
 
-fn merge_top(unsorted: VecDeque<BallData>) -> VecDeque<BallData> {
+impl<E: Copy> QueuePool<BallData<E>> {
+    /// sorts `length` copies of `filler` until the pool stops growing, then grows every queue
+    /// to the largest one of its size class. Every class then holds the queues one sort takes
+    /// from it, all big enough, so a `merge_top` of `length` elements no longer allocates.
+    /// Warm again when the length changes
+    pub fn warm(&mut self, length: usize, filler: BallData<E>) {
+        loop {
+            let before = self.capacity();
+            let mut unsorted = self.take(length);
+            unsorted.extend(core::iter::repeat_n(filler, length));
+            let sorted = merge_top(unsorted, SortOrder::Ascending, self);
+            self.give(sorted);
+            if self.capacity() == before {
+                break;
+            }
+        }
+
+        for queues in self.queues.iter_mut() {
+            let largest = queues.iter().map(VecDeque::capacity).max().unwrap_or(0);
+            for queue in queues.iter_mut() {
+                queue.reserve_exact(largest);
+            }
+        }
+    }
+}
+
+pub fn merge_top<E: Copy>(
+    unsorted: VecDeque<BallData<E>>,
+    order: SortOrder,
+    pool: &mut QueuePool<BallData<E>>,
+) -> VecDeque<BallData<E>> {
     let length = unsorted.len();
 
     if length > 1 {
-        let (half1, half2) = split_queue_alloc(unsorted);
+        let (half1, half2) = split_queue(unsorted, pool);
 
-        let mut half1 = merge_top(half1);
-        let mut half2 = merge_top(half2); // if length is odd, half2 will have one more element
+        let mut half1 = merge_top(half1, order, pool);
+        let mut half2 = merge_top(half2, order, pool); // if length is odd, half2 will have one more element
 
-        let mut merged: VecDeque<BallData> = VecDeque::with_capacity(length);
+        let mut merged = pool.take(length);
 
         for _i in 0..length {
-            merged.push_back(get_smallest(&mut half1, &mut half2));
+            merged.push_back(get_first(&mut half1, &mut half2, order));
         }
 
+        pool.give(half1);
+        pool.give(half2);
+
         return merged;
     }
 
@@ -84,12 +138,15 @@ fn merge_top(unsorted: VecDeque<BallData>) -> VecDeque<BallData> {
     unsorted
 }
 
-fn split_queue_alloc(mut queue: VecDeque<BallData>) -> (VecDeque<BallData>, VecDeque<BallData>) {
+fn split_queue<E: Copy>(
+    mut queue: VecDeque<BallData<E>>,
+    pool: &mut QueuePool<BallData<E>>,
+) -> (VecDeque<BallData<E>>, VecDeque<BallData<E>>) {
     let len = queue.len();
     let half_len = queue.len() / 2;
 
-    let mut half1: VecDeque<BallData> = VecDeque::with_capacity(half_len);
-    let mut half2: VecDeque<BallData> = VecDeque::with_capacity(half_len);
+    let mut half1 = pool.take(half_len);
+    let mut half2 = pool.take(len - half_len);
 
     for _i in 0..half_len {
         half1.push_back(queue.pop_front().unwrap());
@@ -99,10 +156,16 @@ fn split_queue_alloc(mut queue: VecDeque<BallData>) -> (VecDeque<BallData>, VecD
         half2.push_back(queue.pop_front().unwrap());
     }
 
+    pool.give(queue);
+
     (half1, half2)
 }
 
-fn get_smallest(half1: &mut VecDeque<BallData>, half2: &mut VecDeque<BallData>) -> BallData {
+fn get_first<E: Copy>(
+    half1: &mut VecDeque<BallData<E>>,
+    half2: &mut VecDeque<BallData<E>>,
+    order: SortOrder,
+) -> BallData<E> {
     debug_assert!(!half1.is_empty() || !half2.is_empty());
 
     if half1.is_empty() {
@@ -110,10 +173,10 @@ fn get_smallest(half1: &mut VecDeque<BallData>, half2: &mut VecDeque<BallData>)
     } else if half2.is_empty() {
         half1.pop_front().unwrap()
     } else {
-        let e1 = half1.get(0).unwrap();
-        let e2 = half2.get(0).unwrap();
+        let e1 = half1.front().unwrap();
+        let e2 = half2.front().unwrap();
 
-        if e1.distance <= e2.distance {
+        if order.in_order(e1.distance, e2.distance) {
             half1.pop_front().unwrap()
         } else {
             half2.pop_front().unwrap()
@@ -121,37 +184,7 @@ fn get_smallest(half1: &mut VecDeque<BallData>, half2: &mut VecDeque<BallData>)
     }
 }
 
-pub fn bottom_up(
-    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
-    special: Single<&Transform, With<Special>>,
-    exp_params: &Res<ExperimentParameters>,
-    mut writer: MessageWriter<MarkBallMessage>,
-) -> u128 {
-    // https://en.wikipedia.org/wiki/Merge_sort
-
-    let mut ball_list: Vec<BallData> = Vec::with_capacity(exp_params.current_sample_size());
-
-    for ball in balls {
-        ball_list.push(BallData {
-            entity: ball.0,
-            distance: ball.1.translation.distance_squared(special.translation),
-        });
-    }
-
-    let mut temp: Vec<BallData> = allocate_vec_with_placeholders(ball_list.len());
-
-    let start = Instant::now();
-    merge_bottom(&mut ball_list, &mut temp);
-    let elapsed = start.elapsed().as_nanos();
-
-    for i in 0..exp_params.pick_number {
-        writer.write(MarkBallMessage(ball_list[i].entity));
-    }
-
-    elapsed
-}
-
-fn merge_bottom(unsorted: &mut [BallData], mut temp: &mut [BallData]) {
+pub fn merge_bottom<E: Copy>(unsorted: &mut [BallData<E>], temp: &mut [BallData<E>], order: SortOrder) {
     let length = unsorted.len();
 
     let mut run_size = 2;
@@ -159,20 +192,21 @@ fn merge_bottom(unsorted: &mut [BallData], mut temp: &mut [BallData]) {
 
     while run_size <= length {
         while run_start_index < length {
-            merge_run(unsorted, &mut temp, run_start_index, run_size);
+            merge_run(unsorted, temp, run_start_index, run_size, order);
             run_start_index += run_size;
         }
         run_size *= 2;
         run_start_index = 0;
     }
-    merge_run(unsorted, &mut temp, 0, run_size);
+    merge_run(unsorted, temp, 0, run_size, order);
 }
 
-fn merge_run(
-    unsorted: &mut [BallData],
-    temp: &mut [BallData],
+fn merge_run<E: Copy>(
+    unsorted: &mut [BallData<E>],
+    temp: &mut [BallData<E>],
     start: usize,
     run_size: usize,
+    order: SortOrder,
 ) {
     let half_way = min(start + run_size / 2, unsorted.len());
     let end = min(start + run_size, unsorted.len());
@@ -183,109 +217,169 @@ fn merge_run(
     let mut start2 = 0;
     let mut real_run_size = 0;
 
-    for index in 0..run_size {
-        let e1 = half1.get(start1);
-        let e2 = half2.get(start2);
-
+    for (index, slot) in temp.iter_mut().enumerate().take(run_size) {
         if start + index == unsorted.len() {
             break;
-        } else if e1.is_none() {
-            temp[index] = *e2.unwrap();
-            start2 += 1;
-        } else if e2.is_none() {
-            temp[index] = *e1.unwrap();
-            start1 += 1;
-        } else {
-            if e1.unwrap().distance <= e2.unwrap().distance {
-                temp[index] = *e1.unwrap();
+        }
+
+        *slot = match (half1.get(start1), half2.get(start2)) {
+            (Some(e1), Some(e2)) if order.in_order(e1.distance, e2.distance) => {
                 start1 += 1;
-            } else {
-                temp[index] = *e2.unwrap();
+                *e1
+            }
+            (_, Some(e2)) => {
                 start2 += 1;
+                *e2
             }
-        }
+            (Some(e1), None) => {
+                start1 += 1;
+                *e1
+            }
+            (None, None) => unreachable!("a run can not be longer than its halves"),
+        };
 
         real_run_size += 1;
-
-    }
-
-    for index in 0..real_run_size {
-        unsorted[start + index] = temp[index];
-    }
-}
-
-pub fn memory_efficient(
-    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
-    special: Single<&Transform, With<Special>>,
-    exp_params: &Res<ExperimentParameters>,
-    mut writer: MessageWriter<MarkBallMessage>,
-) -> u128 {
-    // https://www.geeksforgeeks.org/dsa/in-place-merge-sort/
-
-    let mut ball_list: Vec<BallData> = Vec::with_capacity(exp_params.current_sample_size());
-
-    for ball in balls {
-        ball_list.push(BallData {
-            entity: ball.0,
-            distance: ball.1.translation.distance_squared(special.translation),
-        })
     }
 
-    let mut temp = allocate_vec_with_placeholders(ball_list.len());
-
-    let start = Instant::now();
-    merge_sort(&mut ball_list[..], &mut temp[..]);
-    let elapsed = start.elapsed().as_nanos();
-
-    for i in 0..exp_params.pick_number {
-        writer.write(MarkBallMessage(ball_list[i].entity));
-    }
-
-    elapsed
+    unsorted[start..start + real_run_size].copy_from_slice(&temp[..real_run_size]);
 }
 
 // start index inclusive, end index exclusive
-fn merge_sort(unsorted: &mut [BallData], temp: &mut [BallData]) {
+pub fn merge_sort<E: Copy>(unsorted: &mut [BallData<E>], temp: &mut [BallData<E>], order: SortOrder) {
     if unsorted.len() > 1 {
         let half_way = unsorted.len() / 2;
 
-        merge_sort(&mut unsorted[..half_way], temp);
-        merge_sort(&mut unsorted[half_way..], temp);
+        merge_sort(&mut unsorted[..half_way], temp, order);
+        merge_sort(&mut unsorted[half_way..], temp, order);
 
-        merge(unsorted, temp, half_way);
+        merge(unsorted, temp, half_way, order);
     }
 }
 
-fn merge(unsorted: &mut [BallData], temp: &mut [BallData], half_way: usize) {
-    debug_assert!(unsorted.len() > 0);
+fn merge<E: Copy>(unsorted: &mut [BallData<E>], temp: &mut [BallData<E>], half_way: usize, order: SortOrder) {
+    debug_assert!(!unsorted.is_empty());
     debug_assert!(temp.len() >= unsorted.len());
 
     let mut start1 = 0;
     let mut start2 = half_way;
     let length = unsorted.len();
 
-    for index in 0..length {
-        let e1 = unsorted.get(start1);
+    for slot in temp.iter_mut().take(length) {
+        // the first half ends at half_way, reading past it would take from the second half
+        let e1 = unsorted[..half_way].get(start1);
         let e2 = unsorted.get(start2);
 
-        if e1.is_none() {
-            temp[index] = *e2.unwrap();
-            start2 += 1;
-        } else if e2.is_none() {
-            temp[index] = *e1.unwrap();
-            start1 += 1;
-        } else {
-            if e1.unwrap().distance <= e2.unwrap().distance {
-                temp[index] = *e1.unwrap();
+        *slot = match (e1, e2) {
+            (Some(e1), Some(e2)) if order.in_order(e1.distance, e2.distance) => {
                 start1 += 1;
-            } else {
-                temp[index] = *e2.unwrap();
+                *e1
+            }
+            (_, Some(e2)) => {
                 start2 += 1;
+                *e2
             }
-        }
+            (Some(e1), None) => {
+                start1 += 1;
+                *e1
+            }
+            (None, None) => unreachable!("merged more elements than the halves hold"),
+        };
+    }
+
+    unsorted.copy_from_slice(&temp[..length]);
+}
+
+pub fn merge_sort_soa(
+    keys: &mut [f32],
+    indices: &mut [u32],
+    temp_keys: &mut [f32],
+    temp_indices: &mut [u32],
+    order: SortOrder,
+) {
+    if keys.len() > 1 {
+        let half_way = keys.len() / 2;
+
+        merge_sort_soa(
+            &mut keys[..half_way],
+            &mut indices[..half_way],
+            temp_keys,
+            temp_indices,
+            order,
+        );
+        merge_sort_soa(
+            &mut keys[half_way..],
+            &mut indices[half_way..],
+            temp_keys,
+            temp_indices,
+            order,
+        );
+
+        merge_soa(keys, indices, temp_keys, temp_indices, half_way, order);
     }
+}
+
+/// merges the sorted runs `[..half_way]` and `[half_way..]` of `keys`,
+/// `indices` is moved along with its keys
+fn merge_soa(
+    keys: &mut [f32],
+    indices: &mut [u32],
+    temp_keys: &mut [f32],
+    temp_indices: &mut [u32],
+    half_way: usize,
+    order: SortOrder,
+) {
+    debug_assert!(keys.len() == indices.len());
+    debug_assert!(temp_keys.len() >= keys.len());
+
+    let length = keys.len();
+    let mut start1 = 0;
+    let mut start2 = half_way;
 
     for index in 0..length {
-        unsorted[index] = temp[index];
+        let take_first =
+            start2 >= length || (start1 < half_way && order.in_order(keys[start1], keys[start2]));
+
+        if take_first {
+            temp_keys[index] = keys[start1];
+            temp_indices[index] = indices[start1];
+            start1 += 1;
+        } else {
+            temp_keys[index] = keys[start2];
+            temp_indices[index] = indices[start2];
+            start2 += 1;
+        }
+    }
+
+    keys.copy_from_slice(&temp_keys[..length]);
+    indices.copy_from_slice(&temp_indices[..length]);
+}
+
+pub fn merge_bottom_soa(
+    keys: &mut [f32],
+    indices: &mut [u32],
+    temp_keys: &mut [f32],
+    temp_indices: &mut [u32],
+    order: SortOrder,
+) {
+    let length = keys.len();
+    let mut run_size = 2;
+
+    // the last pass has run_size >= length and merges the two remaining runs
+    while run_size / 2 < length {
+        let mut run_start = 0;
+        while run_start < length {
+            let end = min(run_start + run_size, length);
+            let half_way = min(run_start + run_size / 2, end);
+            merge_soa(
+                &mut keys[run_start..end],
+                &mut indices[run_start..end],
+                temp_keys,
+                temp_indices,
+                half_way - run_start,
+                order,
+            );
+            run_start += run_size;
+        }
+        run_size *= 2;
     }
 }
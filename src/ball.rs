@@ -1,4 +1,15 @@
+use bevy::color::palettes::basic::*;
+use bevy::math::ops::*;
 use bevy::prelude::*;
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+use crate::experiment::ExperimentParameters;
+use crate::random::SeededRng;
+use crate::scenario::{BallState, Scenario};
+use crate::spawn::SpawnSettings;
+use crate::target::TargetShape;
+use crate::trace::LayoutIndex;
 
 #[derive(Component)]
 pub struct Ball {
@@ -9,6 +20,10 @@ pub struct Ball {
 #[derive(Component)]
 pub struct Special;
 
+/// Sent for every ball picked by the sort, those are colored in
+#[derive(Message)]
+pub struct MarkBallMessage(pub Entity);
+
 #[derive(Bundle)]
 pub struct BallBundle {
     ball: Ball,
@@ -21,12 +36,26 @@ pub struct BallBundle {
 pub struct SpecialBallBundle {
     ball: Ball,
     special: Special,
+    shape: TargetShape,
     mesh: Mesh2d,
     material: MeshMaterial2d<ColorMaterial>,
     transform: Transform,
 }
 
+/// Ball without a mesh or material, used when running headless
+#[derive(Bundle)]
+pub struct HeadlessBallBundle {
+    ball: Ball,
+    transform: Transform,
+}
 
+#[derive(Bundle)]
+pub struct HeadlessSpecialBallBundle {
+    ball: Ball,
+    special: Special,
+    shape: TargetShape,
+    transform: Transform,
+}
 
 pub fn create_ball(
     radius: f32,
@@ -54,6 +83,7 @@ pub fn create_special_ball(
     color: Color,
     transform: Transform,
     vel: Vec2,
+    shape: TargetShape,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
 ) -> SpecialBallBundle {
@@ -65,8 +95,229 @@ pub fn create_special_ball(
     SpecialBallBundle {
         ball,
         special: Special,
+        shape,
         mesh: Mesh2d(mesh),
         material: MeshMaterial2d(material),
         transform,
     }
 }
+
+pub fn create_headless_ball(radius: f32, transform: Transform, vel: Vec2) -> HeadlessBallBundle {
+    HeadlessBallBundle {
+        ball: Ball { radius, velocity: Vec3::new(vel.x, vel.y, 0.) },
+        transform,
+    }
+}
+
+pub fn create_headless_special_ball(
+    radius: f32,
+    transform: Transform,
+    vel: Vec2,
+    shape: TargetShape,
+) -> HeadlessSpecialBallBundle {
+    HeadlessSpecialBallBundle {
+        ball: Ball { radius, velocity: Vec3::new(vel.x, vel.y, 0.) },
+        special: Special,
+        shape,
+        transform,
+    }
+}
+
+/// Size of the area the balls bounce around in, follows the window unless headless
+#[derive(Resource)]
+pub struct Arena(pub Vec2);
+
+pub fn fit_arena_to_window(window: Single<&Window>, mut arena: ResMut<Arena>) {
+    arena.0 = window.size();
+}
+
+pub fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2d);
+}
+
+pub fn clear_balls(balls: Query<Entity, With<Ball>>, mut commands: Commands) {
+    for ball in balls.iter() {
+        commands.entity(ball).despawn();
+    }
+}
+
+fn random_on_circle(rng: &mut impl Rng) -> Vec2 {
+    let angle = rng.random::<f32>() * 2. * PI;
+    Vec2::new(cos(angle), sin(angle))
+}
+
+fn random_layout(
+    size: usize,
+    arena: Vec2,
+    spawn: &SpawnSettings,
+    rng: &mut StdRng,
+) -> Scenario {
+    let radius = 15.;
+    let min_speed = 50.;
+    let max_speed = 100.;
+    // where the center of a ball can be without touching the walls
+    let area = Rect::from_center_half_size(Vec2::ZERO, arena / 2. - radius);
+
+    let random_velocity = |rng: &mut StdRng, speed_factor: f32| {
+        let random_speed = min_speed + (max_speed - min_speed) * rng.random::<f32>();
+        random_on_circle(rng) * random_speed * speed_factor
+    };
+
+    let special_position = area.min + area.size() * Vec2::new(rng.random(), rng.random());
+    let special = BallState {
+        radius,
+        position: special_position,
+        velocity: random_velocity(rng, 3.),
+    };
+
+    let balls = spawn
+        .positions(size, area, special_position, rng)
+        .into_iter()
+        .map(|position| BallState {
+            radius,
+            position,
+            velocity: random_velocity(rng, 1.),
+        })
+        .collect();
+
+    Scenario { special, balls }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn add_ball(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    exp_params: Res<ExperimentParameters>,
+    mut seeded_rng: ResMut<SeededRng>,
+    spawn: Res<SpawnSettings>,
+    scenario: Option<Res<Scenario>>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<ColorMaterial>>>,
+) {
+    let size = exp_params.current_sample_size();
+    let layout = match scenario {
+        // main checked that the scenario has enough balls for every sample size
+        Some(scenario) => Scenario {
+            special: scenario.special,
+            balls: scenario.balls[..size].to_vec(),
+        },
+        None => {
+            // every variation gets the same layout for a sample size
+            seeded_rng.reseed(exp_params.sample_index as u64);
+            random_layout(size, arena.0, &spawn, seeded_rng.rng())
+        }
+    };
+    let transform = |ball: &BallState| Transform::from_translation(ball.position.extend(0.));
+    let special = layout.special;
+
+    // headless runs have no assets to put meshes and materials in
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        commands.spawn_batch(
+            layout
+                .balls
+                .iter()
+                .enumerate()
+                .map(|(i, ball)| {
+                    let bundle = create_headless_ball(ball.radius, transform(ball), ball.velocity);
+                    (bundle, LayoutIndex(i))
+                })
+                .collect::<Vec<_>>(),
+        );
+        commands.spawn(create_headless_special_ball(
+            special.radius,
+            transform(&special),
+            special.velocity,
+            exp_params.target,
+        ));
+        return;
+    };
+
+    let balls: Vec<(BallBundle, LayoutIndex)> = layout
+        .balls
+        .iter()
+        .enumerate()
+        .map(|(i, ball)| {
+            let bundle = create_ball(
+                ball.radius,
+                Color::from(GRAY),
+                transform(ball),
+                ball.velocity,
+                &mut meshes,
+                &mut materials,
+            );
+            (bundle, LayoutIndex(i))
+        })
+        .collect();
+    commands.spawn_batch(balls);
+
+    let special_ball = create_special_ball(
+        special.radius,
+        Color::from(BLACK),
+        transform(&special),
+        special.velocity,
+        exp_params.target,
+        &mut meshes,
+        &mut materials,
+    );
+
+    commands.spawn(special_ball);
+}
+
+fn check_in_bounds(rect: Rect, pos: Vec2, radius: f32) -> usize {
+    if pos.x > rect.max.x - radius {
+        return 1;
+    }
+    if pos.y > rect.max.y - radius {
+        return 2;
+    }
+    if pos.x < rect.min.x + radius {
+        return 3;
+    }
+    if pos.y < rect.min.y + radius {
+        return 4;
+    }
+    0
+}
+
+pub fn move_balls(balls: Query<(&mut Transform, &mut Ball)>, arena: Res<Arena>, time: Res<Time>) {
+    for (mut transform, mut ball) in balls {
+        transform.translation += ball.velocity * time.delta_secs();
+
+        let window_rect = Rect::from_center_size(Vec2::ZERO, arena.0);
+        let trunc_pos = transform.translation.truncate();
+
+        match check_in_bounds(window_rect, trunc_pos, ball.radius) {
+            1 => ball.velocity = ball.velocity.reflect(Vec3::NEG_X),
+            2 => ball.velocity = ball.velocity.reflect(Vec3::NEG_Y),
+            3 => ball.velocity = ball.velocity.reflect(Vec3::X),
+            4 => ball.velocity = ball.velocity.reflect(Vec3::Y),
+            _ => {}
+        }
+
+        let r = Rect::from_center_half_size(
+            // window size adjusted for ball radius
+            window_rect.center(),
+            window_rect.half_size() - ball.radius,
+        );
+        let max_3 = Vec3::new(r.max.x, r.max.y, 0.);
+        let min_3 = Vec3::new(r.min.x, r.min.y, 0.);
+
+        transform.translation = transform.translation.clamp(min_3, max_3);
+    }
+}
+
+pub fn color_marked_balls(
+    mut marked: MessageReader<MarkBallMessage>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    ball_materials: Query<&MeshMaterial2d<ColorMaterial>, Without<Special>>,
+) {
+    for ball_mat in ball_materials {
+        materials.get_mut(ball_mat).unwrap().color = Color::from(GRAY);
+    }
+
+    for e in marked.read() {
+        if let Ok(mat) = ball_materials.get(e.0) {
+            materials.get_mut(mat).unwrap().color = Color::from(RED);
+        }
+    }
+}
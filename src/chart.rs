@@ -0,0 +1,303 @@
+use crate::export::{Export, Exporter, create};
+use crate::profiler::{Cell, Statistic};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::PathBuf;
+
+const WIDTH: f64 = 800.;
+const HEIGHT: f64 = 500.;
+/// left, right, top, bottom space around the plot area
+const MARGIN: (f64, f64, f64, f64) = (80., 190., 40., 60.);
+const COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+];
+
+#[derive(Clone, Copy, Debug)]
+pub struct ChartOptions {
+    /// value drawn for every cell
+    pub statistic: Statistic,
+    /// drawn as ± around the value, `Stddev` or `Ci95` make sense here
+    pub error_bars: Option<Statistic>,
+    pub log_x: bool,
+    pub log_y: bool,
+}
+
+/// A line chart `<file>.svg` with one line per row, the columns are the x values
+pub struct ChartExporter {
+    pub options: ChartOptions,
+    pub directory: PathBuf,
+}
+
+struct Axis {
+    min: f64,
+    max: f64,
+    log: bool,
+}
+
+impl Axis {
+    fn new(values: impl Iterator<Item = f64> + Clone, log: bool) -> Axis {
+        let values = values.filter(|v| v.is_finite() && (!log || *v > 0.));
+        let min = values.clone().fold(f64::INFINITY, f64::min);
+        let max = values.fold(f64::NEG_INFINITY, f64::max);
+        let (mut min, mut max) = if min.is_finite() {
+            (min, max)
+        } else {
+            (1., 10.)
+        };
+        if log {
+            min = 10f64.powf(min.log10().floor());
+            max = 10f64.powf(max.log10().ceil());
+        } else {
+            // linear axes start at zero
+            min = min.min(0.);
+        }
+        if min == max {
+            max = min + 1.;
+        }
+        Axis { min, max, log }
+    }
+
+    /// position between 0 and 1
+    fn fraction(&self, value: f64) -> f64 {
+        if self.log {
+            (value.log10() - self.min.log10()) / (self.max.log10() - self.min.log10())
+        } else {
+            (value - self.min) / (self.max - self.min)
+        }
+    }
+
+    fn ticks(&self) -> Vec<f64> {
+        if self.log {
+            let (first, last) = (self.min.log10() as i32, self.max.log10() as i32);
+            return (first..=last).map(|e| 10f64.powi(e)).collect();
+        }
+        // 1, 2 or 5 times a power of ten, about 5 ticks
+        let rough = (self.max - self.min) / 5.;
+        let magnitude = 10f64.powf(rough.log10().floor());
+        let step = [1., 2., 5., 10.]
+            .into_iter()
+            .map(|m| m * magnitude)
+            .find(|step| *step >= rough)
+            .unwrap_or(rough);
+        let mut ticks = Vec::new();
+        let mut tick = (self.min / step).ceil() * step;
+        while tick <= self.max + step * 1e-9 {
+            ticks.push(tick);
+            tick += step;
+        }
+        ticks
+    }
+}
+
+/// unit that keeps the largest time readable, measurements are in nanoseconds
+fn time_unit(max_ns: f64) -> (&'static str, f64) {
+    match max_ns {
+        m if m >= 1e9 => ("s", 1e9),
+        m if m >= 1e6 => ("ms", 1e6),
+        m if m >= 1e3 => ("µs", 1e3),
+        _ => ("ns", 1.),
+    }
+}
+
+fn label(value: f64) -> String {
+    if value.abs() >= 1e5 || (value != 0. && value.abs() < 1e-2) {
+        format!("{value:e}")
+    } else {
+        format!("{}", (value * 100.).round() / 100.)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl Exporter for ChartExporter {
+    fn export(&self, export: &Export) -> std::io::Result<Vec<PathBuf>> {
+        let table = export.table;
+        let options = self.options;
+
+        // columns are sample sizes, fall back to their position if they are not numbers
+        let xs: Vec<f64> = table
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| column.parse().unwrap_or(i as f64))
+            .collect();
+
+        // (value, error) per row and column, None for empty cells
+        let points: Vec<Vec<Option<(f64, f64)>>> = (0..table.rows.len())
+            .map(|row| {
+                (0..xs.len())
+                    .map(|col| {
+                        let summary = table.cell(row, col).and_then(Cell::summary)?;
+                        let error = options.error_bars.map_or(0., |e| summary.get(e));
+                        Some((summary.get(options.statistic), error))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let extents = points.iter().flatten().flatten();
+        let max_ns = extents.clone().map(|(v, e)| v + e).fold(0., f64::max);
+        let (unit, divisor) = match options.statistic {
+            Statistic::Count => ("", 1.),
+            _ => time_unit(max_ns),
+        };
+        let x_axis = Axis::new(xs.iter().copied(), options.log_x);
+        let y_axis = Axis::new(
+            extents.flat_map(|(v, e)| [(v - e) / divisor, (v + e) / divisor]),
+            options.log_y,
+        );
+
+        let (left, right, top, bottom) = MARGIN;
+        let plot_width = WIDTH - left - right;
+        let plot_height = HEIGHT - top - bottom;
+        let px = |x: f64| left + x_axis.fraction(x) * plot_width;
+        let py = |y: f64| top + (1. - y_axis.fraction(y)) * plot_height;
+        let visible = |x: f64, y: f64| (!options.log_x || x > 0.) && (!options.log_y || y > 0.);
+
+        let mut svg = String::new();
+        // writing to a String can not fail
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" font-family="sans-serif" font-size="12">"#
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="24" text-anchor="middle" font-size="16">{}</text>"#,
+            left + plot_width / 2.,
+            escape(export.table_name)
+        );
+
+        // grid, ticks and axis labels
+        for tick in x_axis.ticks() {
+            let x = px(tick);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{x}" y1="{top}" x2="{x}" y2="{}" stroke="#ddd"/><text x="{x}" y="{}" text-anchor="middle">{}</text>"##,
+                top + plot_height,
+                top + plot_height + 18.,
+                label(tick)
+            );
+        }
+        for tick in y_axis.ticks() {
+            let y = py(tick);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{left}" y1="{y}" x2="{}" y2="{y}" stroke="#ddd"/><text x="{}" y="{}" text-anchor="end">{}</text>"##,
+                left + plot_width,
+                left - 6.,
+                y + 4.,
+                label(tick)
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{left}" y="{top}" width="{plot_width}" height="{plot_height}" fill="none" stroke="black"/>"#
+        );
+        let log_note = |log: bool| if log { ", log scale" } else { "" };
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">sample size (balls{})</text>"#,
+            left + plot_width / 2.,
+            HEIGHT - 16.,
+            log_note(options.log_x)
+        );
+        let y_label = match unit {
+            "" => format!("{}{}", options.statistic.name(), log_note(options.log_y)),
+            unit => format!(
+                "{} time ({unit}{})",
+                options.statistic.name(),
+                log_note(options.log_y)
+            ),
+        };
+        let _ = writeln!(
+            svg,
+            r#"<text transform="translate(20 {}) rotate(-90)" text-anchor="middle">{}</text>"#,
+            top + plot_height / 2.,
+            escape(&y_label)
+        );
+
+        // one line per row, with a legend entry
+        for (row, (name, row_points)) in table.rows.iter().zip(&points).enumerate() {
+            let color = COLORS[row % COLORS.len()];
+            let line: Vec<String> = xs
+                .iter()
+                .zip(row_points)
+                .filter_map(|(x, point)| {
+                    let (value, _) = (*point)?;
+                    let y = value / divisor;
+                    visible(*x, y).then(|| format!("{},{}", px(*x), py(y)))
+                })
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+                line.join(" ")
+            );
+
+            for (x, point) in xs.iter().zip(row_points) {
+                let Some((value, error)) = *point else {
+                    continue;
+                };
+                let y = value / divisor;
+                if !visible(*x, y) {
+                    continue;
+                }
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="3" fill="{color}"/>"#,
+                    px(*x),
+                    py(y)
+                );
+                if error > 0. {
+                    let low = ((value - error) / divisor).max(if options.log_y {
+                        y_axis.min
+                    } else {
+                        f64::MIN
+                    });
+                    let (top_y, bottom_y) = (py((value + error) / divisor), py(low));
+                    let _ = writeln!(
+                        svg,
+                        r#"<path d="M{0} {1}V{2}M{3} {1}H{4}M{3} {2}H{4}" stroke="{color}"/>"#,
+                        px(*x),
+                        top_y,
+                        bottom_y,
+                        px(*x) - 4.,
+                        px(*x) + 4.
+                    );
+                }
+            }
+
+            let legend_y = top + 10. + row as f64 * 20.;
+            let legend_x = left + plot_width + 16.;
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{legend_x}" y1="{legend_y}" x2="{}" y2="{legend_y}" stroke="{color}" stroke-width="2"/><text x="{}" y="{}">{}</text>"#,
+                legend_x + 20.,
+                legend_x + 26.,
+                legend_y + 4.,
+                escape(name)
+            );
+        }
+        if let Some(error_bars) = options.error_bars {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="10">error bars: ± {}</text>"#,
+                left + plot_width + 16.,
+                top + plot_height,
+                error_bars.name()
+            );
+        }
+        let _ = writeln!(svg, "</svg>");
+
+        let file_name = format!("{}.svg", export.file_name);
+        let (path, mut file) = create(&self.directory, &file_name)?;
+        file.write_all(svg.as_bytes())?;
+        file.flush()?;
+        Ok(vec![path])
+    }
+}
@@ -0,0 +1,217 @@
+use crate::profiler::Summary;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Every measurement of a `<table>_raw.csv` file, by row and column
+pub struct Measurements {
+    pub cells: HashMap<(String, String), Vec<u128>>,
+    /// the cells in the order they first appear in the file
+    pub order: Vec<(String, String)>,
+}
+
+/// splits one csv line, handling quoted fields
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+impl Measurements {
+    pub fn read_raw_csv(path: &Path) -> Result<Measurements, String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+        let text = std::fs::read_to_string(path).map_err(|e| error(&e))?;
+
+        let mut lines = text.lines();
+        if lines.next().map(parse_csv_line)
+            != Some(vec!["row".into(), "column".into(), "value".into()])
+        {
+            return Err(error(&"not a raw measurement file, write one with --raw"));
+        }
+
+        let mut cells: HashMap<(String, String), Vec<u128>> = HashMap::new();
+        let mut order = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let [row, column, value]: [String; 3] = parse_csv_line(line)
+                .try_into()
+                .map_err(|_| error(&format!("line {} does not have 3 fields", i + 2)))?;
+            let value = value
+                .parse()
+                .map_err(|e| error(&format!("line {}: {e}", i + 2)))?;
+
+            let key = (row, column);
+            if !cells.contains_key(&key) {
+                order.push(key.clone());
+            }
+            cells.entry(key).or_default().push(value);
+        }
+        Ok(Measurements { cells, order })
+    }
+
+    pub fn get(&self, row: &str, column: &str) -> Option<&[u128]> {
+        self.cells
+            .get(&(row.to_string(), column.to_string()))
+            .map(Vec::as_slice)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    Improved,
+    Regressed,
+    /// the difference is within the noise
+    NoChange,
+}
+
+impl Verdict {
+    pub fn name(&self) -> String {
+        match self {
+            Verdict::Improved => "improved".to_string(),
+            Verdict::Regressed => "regressed".to_string(),
+            Verdict::NoChange => "no change".to_string(),
+        }
+    }
+}
+
+pub struct Comparison {
+    pub row: String,
+    pub column: String,
+    pub base: Summary,
+    pub new: Summary,
+    /// change of the mean in percent, positive is slower,
+    /// none if the base mean is zero
+    pub change: Option<f64>,
+    /// welch's t statistic, infinite if the means differ but neither file varies
+    pub t: f64,
+    /// welch–satterthwaite degrees of freedom of `t`
+    pub degrees_of_freedom: f64,
+    pub verdict: Verdict,
+}
+
+/// two sided 95% critical values of student's t for 1 to 30 degrees of freedom
+const CRITICAL_T: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// |t| above which a difference counts as significant at 95%,
+/// fractional degrees of freedom round down so small cells are judged strictly
+fn critical_t(degrees_of_freedom: f64) -> f64 {
+    match degrees_of_freedom.floor() as usize {
+        0 => f64::INFINITY,
+        df @ 1..=30 => CRITICAL_T[df - 1],
+        31..40 => 2.042,
+        40..60 => 2.021,
+        60..120 => 2.000,
+        120..1000 => 1.980,
+        _ => 1.960,
+    }
+}
+
+/// compares every cell that is in both files, cells with fewer than two measurements are skipped
+pub fn compare(base: &Measurements, new: &Measurements) -> Vec<Comparison> {
+    let mut comparisons = Vec::new();
+    for (row, column) in &base.order {
+        let base_values = &base.cells[&(row.clone(), column.clone())];
+        let Some(new_values) = new.get(row, column) else {
+            continue;
+        };
+        let (Some(base), Some(new)) = (Summary::new(base_values), Summary::new(new_values)) else {
+            continue;
+        };
+        if base.count < 2 || new.count < 2 {
+            continue;
+        }
+
+        let change = (base.mean != 0.).then(|| (new.mean - base.mean) / base.mean * 100.);
+        // squared standard errors of the means
+        let base_error = base.stddev.powi(2) / base.count as f64;
+        let new_error = new.stddev.powi(2) / new.count as f64;
+        let error = (base_error + new_error).sqrt();
+        let difference = new.mean - base.mean;
+        let (t, degrees_of_freedom) = if error > 0. {
+            let degrees_of_freedom = (base_error + new_error).powi(2)
+                / (base_error.powi(2) / (base.count - 1) as f64
+                    + new_error.powi(2) / (new.count - 1) as f64);
+            (difference / error, degrees_of_freedom)
+        } else {
+            // no noise at all, any difference is real
+            let t = if difference == 0. {
+                0.
+            } else {
+                difference.signum() * f64::INFINITY
+            };
+            (t, (base.count + new.count - 2) as f64)
+        };
+        let critical = critical_t(degrees_of_freedom);
+        let verdict = match t {
+            t if t > critical => Verdict::Regressed,
+            t if t < -critical => Verdict::Improved,
+            _ => Verdict::NoChange,
+        };
+
+        comparisons.push(Comparison {
+            row: row.clone(),
+            column: column.clone(),
+            base,
+            new,
+            change,
+            t,
+            degrees_of_freedom,
+            verdict,
+        });
+    }
+    comparisons
+}
+
+/// compares the raw measurement files `base` and `new` and prints every cell,
+/// returns true if a significant regression is larger than `threshold` percent
+pub fn compare_files(base: &Path, new: &Path, threshold: f64) -> Result<bool, String> {
+    let comparisons = compare(
+        &Measurements::read_raw_csv(base)?,
+        &Measurements::read_raw_csv(new)?,
+    );
+    if comparisons.is_empty() {
+        return Err("the files have no cells in common with at least two measurements".to_string());
+    }
+
+    let mut failed = false;
+    for c in comparisons.iter() {
+        // any slowdown from nothing is over the threshold
+        let over_threshold =
+            c.verdict == Verdict::Regressed && c.change.is_none_or(|change| change > threshold);
+        failed |= over_threshold;
+        println!(
+            "{:<40} base {:>12.0} ns  new {:>12.0} ns  {:>8}  t {:+6.2} df {:5.1}  {}{}",
+            format!("{}/{}", c.row, c.column),
+            c.base.mean,
+            c.new.mean,
+            c.change.map_or("n/a".to_string(), |change| format!("{change:+.1}%")),
+            c.t,
+            c.degrees_of_freedom,
+            c.verdict.name(),
+            if over_threshold { "  over threshold" } else { "" }
+        );
+    }
+
+    let count = |verdict: Verdict| comparisons.iter().filter(|c| c.verdict == verdict).count();
+    println!(
+        "{} improved, {} regressed, {} no change",
+        count(Verdict::Improved),
+        count(Verdict::Regressed),
+        count(Verdict::NoChange)
+    );
+    Ok(failed)
+}
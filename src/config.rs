@@ -0,0 +1,103 @@
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command, Parser};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+// An experiment file is a TOML table whose keys are the long command line options,
+// with `-` or `_` between words:
+//
+//   first = 100
+//   step = 100
+//   algorithms = ["BottomUp", "BottomUpSoA"]
+//   timing-scope = "gather-sort"
+//   format = ["csv", "svg"]
+//   seed = 42
+//
+// Options given on the command line win over the file.
+
+/// Parses `command_line` (program name first), filling in options missing from it
+/// with the experiment file that `config` picks from the parsed arguments.
+/// Like `Parser::parse_from`, invalid arguments print the usage and exit
+pub fn parse_with_config<P: Parser>(
+    command_line: Vec<OsString>,
+    config: impl Fn(&P) -> Option<&PathBuf>,
+) -> Result<P, String> {
+    let matches = P::command().get_matches_from(&command_line);
+    let args = P::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let Some(path) = config(&args) else { return Ok(args) };
+
+    let file_args = config_args(path, &P::command(), &matches)?;
+    let mut command_line = command_line.into_iter();
+    let program = command_line.next().unwrap_or_default();
+    Ok(P::parse_from(
+        std::iter::once(program)
+            .chain(file_args.into_iter().map(OsString::from))
+            .chain(command_line),
+    ))
+}
+
+/// Turns the experiment file at `path` into command line arguments for every option
+/// that `matches` did not get from the command line
+pub fn config_args(
+    path: &Path,
+    command: &Command,
+    matches: &ArgMatches,
+) -> Result<Vec<String>, String> {
+    let error = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+    let text = std::fs::read_to_string(path).map_err(|e| error(&e))?;
+    let table: toml::Table = text.parse().map_err(|e| error(&e))?;
+
+    let mut args = Vec::new();
+    for (key, value) in table {
+        let long = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()))
+            .ok_or_else(|| error(&format!("unknown option {key}")))?;
+        if long == "config" {
+            return Err(error(&"an experiment file can not include another"));
+        }
+        if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+
+        if let ArgAction::SetTrue = arg.get_action() {
+            // a flag can only be turned on, false is only right if it is off already
+            let on_by_default = arg.get_default_values().iter().any(|v| v.to_str() == Some("true"));
+            match value {
+                Value::Boolean(true) => args.push(format!("--{long}")),
+                Value::Boolean(false) if !on_by_default => {}
+                Value::Boolean(false) => {
+                    return Err(error(&format!("{key} is on by default and can not be turned off")));
+                }
+                _ => return Err(error(&format!("{key} must be true or false"))),
+            }
+            continue;
+        }
+
+        let values = match value {
+            Value::Array(values) => values.iter().map(scalar).collect::<Option<Vec<_>>>(),
+            value => scalar(&value).map(|value| vec![value]),
+        }
+        .ok_or_else(|| error(&format!("{key} must be a value or a list of values")))?;
+
+        match arg.get_value_delimiter() {
+            Some(delimiter) => {
+                args.push(format!("--{long}={}", values.join(&delimiter.to_string())))
+            }
+            None => args.extend(values.iter().map(|value| format!("--{long}={value}"))),
+        }
+    }
+    Ok(args)
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(i) => Some(i.to_string()),
+        Value::Float(f) => Some(f.to_string()),
+        Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,127 @@
+use crate::algorithms::SortOrder;
+use crate::ball::{Ball, MarkBallMessage, Special};
+use crate::experiment::ExperimentParameters;
+use crate::key::ball_key;
+use crate::sorting::mark_picked;
+use crate::target::TargetShape;
+use crate::timing::PhaseTimer;
+use bevy::prelude::*;
+use libloading::Library;
+use std::ffi::{CStr, c_char};
+use std::path::Path;
+
+// Plugin interface for sorting algorithms living in a dynamic library.
+// A plugin exports `sort_plugin_descriptor` returning a `SortPluginDescriptor`,
+// see plugins/insertion_sort for an example.
+
+/// Bumped whenever `SortPair` or `SortPluginDescriptor` change
+pub const SORT_PLUGIN_ABI_VERSION: u32 = 2;
+
+const DESCRIPTOR_SYMBOL: &[u8] = b"sort_plugin_descriptor\0";
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SortPair {
+    /// bits of the ball's `Entity`, must be left untouched
+    pub entity: u64,
+    pub key: f32,
+}
+
+#[repr(C)]
+pub struct SortPluginDescriptor {
+    pub abi_version: u32,
+    /// nul terminated name of the algorithm, must live as long as the library
+    pub name: *const c_char,
+    /// sorts `len` pairs in place by ascending key, or descending if `descending` is set
+    pub sort: unsafe extern "C" fn(pairs: *mut SortPair, len: usize, descending: bool),
+}
+
+type DescriptorFn = unsafe extern "C" fn() -> SortPluginDescriptor;
+
+pub struct DynamicSort {
+    pub name: String,
+    sort: unsafe extern "C" fn(*mut SortPair, usize, bool),
+    // keeps `sort` valid, must be dropped last
+    _library: Library,
+}
+
+impl DynamicSort {
+    pub fn load(path: &Path) -> Result<DynamicSort, String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+
+        // SAFETY: loading runs the library's initializers, plugins are trusted code
+        let library = unsafe { Library::new(path) }.map_err(|e| error(&e))?;
+        // SAFETY: the symbol is declared with the signature of `DescriptorFn`
+        let descriptor = unsafe {
+            let descriptor_fn = library
+                .get::<DescriptorFn>(DESCRIPTOR_SYMBOL)
+                .map_err(|e| error(&e))?;
+            descriptor_fn()
+        };
+
+        if descriptor.abi_version != SORT_PLUGIN_ABI_VERSION {
+            return Err(error(&format!(
+                "plugin abi version {} but expected {SORT_PLUGIN_ABI_VERSION}",
+                descriptor.abi_version
+            )));
+        }
+        if descriptor.name.is_null() {
+            return Err(error(&"plugin has no name"));
+        }
+        // SAFETY: checked for null, the plugin promises a nul terminated string
+        let name = unsafe { CStr::from_ptr(descriptor.name) }.to_string_lossy().into_owned();
+
+        Ok(DynamicSort {
+            name,
+            sort: descriptor.sort,
+            _library: library,
+        })
+    }
+}
+
+/// Sorting algorithms loaded at startup, they run after the built in variations
+#[derive(Resource, Default)]
+pub struct DynamicSorts(pub Vec<DynamicSort>);
+
+impl DynamicSorts {
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<DynamicSorts, String> {
+        let mut sorts = Vec::with_capacity(paths.len());
+        for path in paths {
+            sorts.push(DynamicSort::load(path.as_ref())?);
+        }
+        Ok(DynamicSorts(sorts))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0.iter().map(|sort| sort.name.clone()).collect()
+    }
+}
+
+pub fn dynamic(
+    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &TargetShape), With<Special>>,
+    exp_params: &Res<ExperimentParameters>,
+    sort: &DynamicSort,
+    timer: &mut PhaseTimer,
+    mut writer: MessageWriter<MarkBallMessage>,
+) {
+    let mut pairs: Vec<SortPair> = Vec::with_capacity(exp_params.current_sample_size());
+
+    for ball in balls {
+        pairs.push(SortPair {
+            entity: ball.0.to_bits(),
+            key: ball_key(exp_params, ball.1, ball.2, *special),
+        });
+    }
+
+    timer.end_gather();
+
+    let descending = exp_params.order == SortOrder::Descending;
+    // SAFETY: the pointer and length describe `pairs`, which outlives the call
+    unsafe { (sort.sort)(pairs.as_mut_ptr(), pairs.len(), descending) };
+    timer.end_sort();
+
+    // a misbehaving plugin could hand back bits that are not an entity
+    let sorted = pairs.iter().filter_map(|pair| Entity::try_from_bits(pair.entity));
+    mark_picked(sorted, exp_params, &mut writer);
+}
@@ -1,42 +1,90 @@
 use std::time::{Duration, Instant};
 use bevy::prelude::*;
+use clap::ValueEnum;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use crate::algorithms::SortOrder;
+use crate::key::SortKey;
+use crate::metric::Metric;
+use crate::target::TargetShape;
 
 
+/// Order the (variation, sample size) blocks of an experiment run in
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum BlockOrder {
+    /// every sample size for the first variation, then the next variation
+    Sequential,
+    /// every variation for the first sample size, then the next sample size
+    Interleaved,
+    /// all blocks shuffled with the seed
+    Randomized,
+}
 
+impl BlockOrder {
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
 
+/// How the sample sizes grow from one step to the next
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Progression {
+    /// first, first + step, first + 2 step, ...
+    Arithmetic,
+    /// first, first · ratio, first · ratio², ...
+    Geometric,
+    /// evenly spaced on a log scale from first to last
+    Log,
+    /// powers of two, starting at the first one not below first
+    Pow2,
+}
+
+impl Progression {
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
 
 pub struct ExperimentPlugin {
-    pub first: usize,
-    pub step: usize,
+    /// one step per sample size, in order
+    pub sample_sizes: Vec<usize>,
     pub variations: usize,
-    pub number_of_steps: usize,
     pub step_duration: Duration,
     pub min_calcs_per_step: usize,
+    /// frames at the start of every step that are sorted but not measured
+    pub warmup_frames: usize,
+    /// time at the start of every step that is sorted but not measured
+    pub warmup_duration: Duration,
     pub pick_number: usize,
+    pub key: SortKey,
+    pub metric: Metric,
+    pub target: TargetShape,
+    pub order: SortOrder,
+    pub farthest: bool,
+    pub block_order: BlockOrder,
+    /// shuffles the blocks for `BlockOrder::Randomized`
+    pub seed: u64,
     pub debug: bool,
 }
 
 impl Plugin for ExperimentPlugin {
     fn build(&self, app: &mut App) {
         app.add_message::<ExperimentProgress>();
-        app.insert_resource(ExperimentParameters::new(
-            self.first,
-            self.step,
-            self.variations,
-            self.number_of_steps,
-            self.step_duration,
-            self.min_calcs_per_step,
-            self.pick_number,
-            self.debug,
-        ));
-        app.add_systems(Update, progress_experiment);
+        app.insert_resource(ExperimentParameters::new(self));
+        // after every system in Update, so the frame that ends a warmup or a block
+        // is counted once the sort has seen it
+        app.add_systems(
+            PostUpdate,
+            (progress_experiment, process_experiment_progress).chain(),
+        );
         app.add_systems(PreStartup, record_start_up_instant);
     }
 }
 
 #[derive(Resource)]
 pub struct ExperimentParameters {
-    pub sample_sizes: [usize; 100],
+    pub sample_sizes: Vec<usize>,
     pub sample_sizes_as_str: Vec<String>,
     pub sample_index: usize,
     pub number_samples: usize,
@@ -44,42 +92,63 @@ pub struct ExperimentParameters {
     current_sample_progress: Duration,
     min_calcs_per_sample: usize,
     current_calcs: usize,
+    pub warmup_frames: usize,
+    pub warmup_duration: Duration,
+    current_warmup_frames: usize,
+    current_warmup_progress: Duration,
     pub variation_index: usize,
     pub number_variations: usize,
+    pub block_order: BlockOrder,
+    /// (variation, sample) blocks in the order they run
+    schedule: Vec<(usize, usize)>,
+    block_index: usize,
     pub pick_number: usize,
+    pub key: SortKey,
+    pub metric: Metric,
+    pub target: TargetShape,
+    pub order: SortOrder,
+    /// mark the farthest balls instead of the nearest
+    pub farthest: bool,
     pub debug: bool,
 }
 
 impl ExperimentParameters {
-    fn new(
-        first: usize,
-        step: usize,
-        number_variations: usize,
-        number_samples: usize,
-        sample_duration: Duration,
-        min_calcs_per_sample: usize,
-        pick_number: usize,
-        debug: bool,
-    ) -> ExperimentParameters {
-        let sample_sizes = generate_sample_sizes(first, step);
-        let mut sample_sizes_as_str: Vec<String> = Vec::with_capacity(100);
-        for sample_size in sample_sizes.iter() {
+    fn new(plugin: &ExperimentPlugin) -> ExperimentParameters {
+        let number_samples = plugin.sample_sizes.len();
+        let mut sample_sizes_as_str: Vec<String> = Vec::with_capacity(number_samples);
+        for sample_size in plugin.sample_sizes.iter() {
             sample_sizes_as_str.push(sample_size.to_string());
         }
 
+        let schedule =
+            generate_schedule(plugin.block_order, plugin.variations, number_samples, plugin.seed);
+        let (variation_index, sample_index) = schedule.first().copied().unwrap_or_default();
+
         ExperimentParameters {
-            sample_sizes,
+            sample_sizes: plugin.sample_sizes.clone(),
             sample_sizes_as_str,
             number_samples,
-            sample_index: 0,
-            number_variations,
-            variation_index: 0,
-            sample_duration,
+            sample_index,
+            number_variations: plugin.variations,
+            variation_index,
+            block_order: plugin.block_order,
+            schedule,
+            block_index: 0,
+            sample_duration: plugin.step_duration,
             current_sample_progress: Duration::from_secs(0),
-            min_calcs_per_sample,
+            min_calcs_per_sample: plugin.min_calcs_per_step,
             current_calcs: 0,
-            pick_number,
-            debug,
+            warmup_frames: plugin.warmup_frames,
+            warmup_duration: plugin.warmup_duration,
+            current_warmup_frames: 0,
+            current_warmup_progress: Duration::from_secs(0),
+            pick_number: plugin.pick_number,
+            key: plugin.key,
+            metric: plugin.metric,
+            target: plugin.target,
+            order: plugin.order,
+            farthest: plugin.farthest,
+            debug: plugin.debug,
         }
     }
 
@@ -92,38 +161,136 @@ impl ExperimentParameters {
         self.sample_sizes_as_str[self.sample_index].clone()
     }
 
-    /// return false if there is no next variation
-    pub fn next_variation(&mut self) -> bool {
-        self.variation_index += 1;
-        self.variation_index < self.number_variations
+    /// true until both the warmup frames and the warmup duration of the current block have passed,
+    /// the sort still runs but nothing is recorded
+    pub fn warming_up(&self) -> bool {
+        self.current_warmup_frames < self.warmup_frames
+            || self.current_warmup_progress < self.warmup_duration
     }
 
-    /// return false if there is no next sample
-    pub fn next_sample(&mut self) -> bool {
-        self.sample_index += 1;
-        self.sample_index < self.number_samples
+    /// return false if there is no next block
+    pub fn next_block(&mut self) -> bool {
+        if self.block_index + 1 >= self.schedule.len() {
+            return false;
+        }
+        self.block_index += 1;
+        (self.variation_index, self.sample_index) = self.schedule[self.block_index];
+        true
     }
 
     pub fn relevant_samples(&self) -> Vec<String> {
-        Vec::from(&self.sample_sizes_as_str[..self.number_samples])
+        self.sample_sizes_as_str.clone()
     }
 }
 
-fn generate_sample_sizes(first: usize, step: usize) -> [usize; 100] {
-    let mut array: [usize; 100] = [0; 100];
+/// `count` sample sizes following `progression`, every size is larger than the one before.
+/// `step` is only used by `Arithmetic`, `ratio` by `Geometric` and `last` by `Log`.
+/// Stops early instead of overflowing, parameters that can not grow are an error
+pub fn generate_sample_sizes(
+    progression: Progression,
+    first: usize,
+    step: usize,
+    ratio: f64,
+    last: usize,
+    count: usize,
+) -> Result<Vec<usize>, String> {
+    match progression {
+        Progression::Arithmetic if step == 0 => {
+            return Err("the arithmetic progression needs a step of at least 1".to_string());
+        }
+        Progression::Geometric if first == 0 || !(ratio > 1. && ratio.is_finite()) => {
+            return Err(format!(
+                "the geometric progression needs a first size of at least 1 \
+                 and a ratio above 1, got {first} and {ratio}"
+            ));
+        }
+        Progression::Log if first == 0 || first >= last => {
+            return Err(format!(
+                "the log progression needs 1 <= first < last, got {first} and {last}"
+            ));
+        }
+        Progression::Log if count > last - first + 1 => {
+            return Err(format!(
+                "the log progression from {first} to {last} has {} sizes, {count} were asked for",
+                last - first + 1
+            ));
+        }
+        _ => {}
+    }
 
-    #[allow(clippy::needless_range_loop)]
-    for i in 0..100 {
-        array[i] = first + (step * i);
+    // `count` is not bounded and pow2 or geometric stop after a few dozen sizes anyway
+    let mut sizes: Vec<usize> = Vec::new();
+    for i in 0..count {
+        let size = match progression {
+            Progression::Arithmetic => {
+                let Some(size) = step.checked_mul(i).and_then(|s| s.checked_add(first)) else { break };
+                size
+            }
+            Progression::Geometric => {
+                let size = (first as f64 * ratio.powi(i as i32)).round();
+                if size >= usize::MAX as f64 { break }
+                size as usize
+            }
+            Progression::Log if count == 1 => first,
+            Progression::Log => {
+                let (low, high) = ((first as f64).ln(), (last as f64).ln());
+                (low + (high - low) * i as f64 / (count - 1) as f64).exp().round() as usize
+            }
+            Progression::Pow2 => {
+                let doubled = 1usize.checked_shl(i as u32).and_then(|factor| {
+                    first.checked_next_power_of_two()?.checked_mul(factor)
+                });
+                let Some(size) = doubled else { break };
+                size
+            }
+        };
+        // small ratios or many log steps round to the same size, move those up by one.
+        // The log steps are at least one apart on average and grow, so this stays below last
+        let size = match sizes.last() {
+            Some(&previous) if size <= previous => match previous.checked_add(1) {
+                Some(size) => size,
+                None => break,
+            },
+            _ => size,
+        };
+        sizes.push(size);
     }
+    Ok(sizes)
+}
 
-    array
+/// explicit sample sizes name the columns of every table, so they have to be unique
+pub fn check_sample_sizes(sizes: &[usize]) -> Result<(), String> {
+    for (i, size) in sizes.iter().enumerate() {
+        if sizes[..i].contains(size) {
+            return Err(format!("the sample size {size} is given more than once"));
+        }
+    }
+    Ok(())
 }
 
+fn generate_schedule(
+    block_order: BlockOrder,
+    variations: usize,
+    samples: usize,
+    seed: u64,
+) -> Vec<(usize, usize)> {
+    let mut schedule: Vec<(usize, usize)> = match block_order {
+        BlockOrder::Interleaved => (0..samples)
+            .flat_map(|sample| (0..variations).map(move |variation| (variation, sample)))
+            .collect(),
+        _ => (0..variations)
+            .flat_map(|variation| (0..samples).map(move |sample| (variation, sample)))
+            .collect(),
+    };
+    if block_order == BlockOrder::Randomized {
+        schedule.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+    schedule
+}
 
 /// * `0`: previous sample size index
 /// * `1`: previous variation index
-/// * `2`: is last sample for experiment
+/// * `2`: is last block for experiment
 #[derive(Message)]
 pub struct ExperimentProgress(pub usize, pub usize, pub bool);
 
@@ -132,6 +299,12 @@ fn progress_experiment(
     mut writer: MessageWriter<ExperimentProgress>,
     time: Res<Time>,
 ) {
+    if parameters.warming_up() {
+        parameters.current_warmup_frames += 1;
+        parameters.current_warmup_progress += Duration::from_secs_f32(time.delta_secs());
+        return;
+    }
+
     parameters.current_calcs += 1;
     parameters.current_sample_progress += Duration::from_secs_f32(time.delta_secs());
     let duration_over = parameters.current_sample_progress >= parameters.sample_duration;
@@ -141,22 +314,32 @@ fn progress_experiment(
     let prev_sample_size_index = parameters.sample_index;
     let prev_variation_index = parameters.variation_index;
 
-    let last_sample = !parameters.next_sample(); // increment sample index
-    let mut last_variation = false;
-    if last_sample {
-        last_variation = !parameters.next_variation(); // increment variation index
-        parameters.sample_index = 0; // reset sample index
-    }
+    let last_block = !parameters.next_block();
 
     parameters.current_sample_progress = Duration::from_secs(0);
     parameters.current_calcs = 0;
+    parameters.current_warmup_frames = 0;
+    parameters.current_warmup_progress = Duration::from_secs(0);
     writer.write(ExperimentProgress(
         prev_sample_size_index,
         prev_variation_index,
-        last_sample && last_variation,
+        last_block,
     ));
 }
 
+/// exits the app after the last block, systems writing results run after it
+pub fn process_experiment_progress(
+    mut progress: MessageReader<ExperimentProgress>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    for progress in progress.read() {
+        // let (prev_size, prev_var, last_sample) = (progress.0, progress.1, progress.2);
+        if progress.2 {
+            exit.write(AppExit::Success);
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct StartupInstant(pub Instant);
 
@@ -0,0 +1,318 @@
+use crate::chart::{ChartExporter, ChartOptions};
+use crate::profiler::{Statistic, Summary, Table};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Directory the exporters write to unless another one is given
+pub const OUTPUT_DIR: &str = "csv";
+
+/// File format a table is exported in
+#[cfg_attr(feature = "bevy", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    Markdown,
+    /// line chart of the first statistic
+    Svg,
+}
+
+/// Settings shared by every format
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    pub statistics: Vec<Statistic>,
+    /// include every measurement
+    pub raw: bool,
+    pub chart: ChartOptions,
+    /// every file goes here, created if it does not exist
+    pub directory: PathBuf,
+}
+
+impl Format {
+    pub fn exporter(&self, options: &ExportOptions) -> Box<dyn Exporter> {
+        let statistics = options.statistics.clone();
+        let raw = options.raw;
+        let directory = options.directory.clone();
+        match self {
+            Format::Csv => Box::new(CsvExporter {
+                statistics,
+                raw,
+                directory,
+            }),
+            Format::Json => Box::new(JsonExporter {
+                statistics,
+                raw,
+                directory,
+            }),
+            Format::Markdown => Box::new(MarkdownExporter {
+                statistics,
+                directory,
+            }),
+            Format::Svg => Box::new(ChartExporter {
+                options: options.chart,
+                directory,
+            }),
+        }
+    }
+}
+
+/// Everything an exporter needs to know about one table
+pub struct Export<'a> {
+    pub table_name: &'a str,
+    pub table: &'a Table,
+    pub metadata: &'a [(String, String)],
+    /// file name without directory or extension
+    pub file_name: &'a str,
+}
+
+pub trait Exporter {
+    /// writes the table to one or more files in the directory of the exporter,
+    /// returns the paths of the files
+    fn export(&self, export: &Export) -> std::io::Result<Vec<PathBuf>>;
+}
+
+/// creates `file_name` in `directory` and the directory itself if needed
+pub(crate) fn create(
+    directory: &Path,
+    file_name: &str,
+) -> std::io::Result<(PathBuf, BufWriter<File>)> {
+    std::fs::create_dir_all(directory)?;
+    let path = directory.join(file_name);
+    let file = BufWriter::new(File::create(&path)?);
+    Ok((path, file))
+}
+
+/// One file per statistic, the mean keeps the plain file name.
+/// Metadata goes to `<file>_metadata.csv` and measurements to `<file>_raw.csv` if `raw` is set.
+pub struct CsvExporter {
+    pub statistics: Vec<Statistic>,
+    pub raw: bool,
+    pub directory: PathBuf,
+}
+
+/// quotes a field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_line(
+    file: &mut impl Write,
+    fields: impl IntoIterator<Item = String>,
+) -> std::io::Result<()> {
+    let fields: Vec<String> = fields.into_iter().map(|f| csv_field(&f)).collect();
+    writeln!(file, "{}", fields.join(","))
+}
+
+impl Exporter for CsvExporter {
+    fn export(&self, export: &Export) -> std::io::Result<Vec<PathBuf>> {
+        let table = export.table;
+        let mut written = Vec::new();
+
+        for statistic in &self.statistics {
+            let file_name = match statistic {
+                Statistic::Mean => format!("{}.csv", export.file_name),
+                _ => format!("{}_{}.csv", export.file_name, statistic.name()),
+            };
+            let (path, mut file) = create(&self.directory, &file_name)?;
+
+            let header =
+                std::iter::once(export.table_name.to_string()).chain(table.columns.clone());
+            csv_line(&mut file, header)?;
+            for (row_name, row) in table.rows.iter().zip(table.get_statistic(*statistic)) {
+                let cells = row.iter().map(|cell| cell.to_string());
+                csv_line(&mut file, std::iter::once(row_name.clone()).chain(cells))?;
+            }
+            file.flush()?;
+            written.push(path);
+        }
+
+        if !export.metadata.is_empty() {
+            let file_name = format!("{}_metadata.csv", export.file_name);
+            let (path, mut file) = create(&self.directory, &file_name)?;
+            for (key, value) in export.metadata {
+                csv_line(&mut file, [key.clone(), value.clone()])?;
+            }
+            file.flush()?;
+            written.push(path);
+        }
+
+        if self.raw {
+            let file_name = format!("{}_raw.csv", export.file_name);
+            let (path, mut file) = create(&self.directory, &file_name)?;
+            csv_line(&mut file, ["row", "column", "value"].map(String::from))?;
+            for (row_index, row) in table.rows.iter().enumerate() {
+                for (column_index, column) in table.columns.iter().enumerate() {
+                    let Some(cell) = table.cell(row_index, column_index) else {
+                        continue;
+                    };
+                    for value in cell.samples() {
+                        csv_line(&mut file, [row.clone(), column.clone(), value.to_string()])?;
+                    }
+                }
+            }
+            file.flush()?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+}
+
+/// A single `<file>.json` with the metadata, the chosen statistics of every cell
+/// and the measurements if `raw` is set
+pub struct JsonExporter {
+    pub statistics: Vec<Statistic>,
+    pub raw: bool,
+    pub directory: PathBuf,
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// json has no infinities or NaN
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+impl Exporter for JsonExporter {
+    fn export(&self, export: &Export) -> std::io::Result<Vec<PathBuf>> {
+        let table = export.table;
+        let file_name = format!("{}.json", export.file_name);
+        let (path, mut file) = create(&self.directory, &file_name)?;
+
+        let metadata: Vec<String> = export
+            .metadata
+            .iter()
+            .map(|(key, value)| format!("{}: {}", json_string(key), json_string(value)))
+            .collect();
+        let columns: Vec<String> = table.columns.iter().map(|c| json_string(c)).collect();
+
+        writeln!(file, "{{")?;
+        writeln!(file, "  \"table\": {},", json_string(export.table_name))?;
+        writeln!(file, "  \"metadata\": {{{}}},", metadata.join(", "))?;
+        writeln!(file, "  \"columns\": [{}],", columns.join(", "))?;
+        writeln!(file, "  \"rows\": [")?;
+        for (row_index, row) in table.rows.iter().enumerate() {
+            writeln!(file, "    {{")?;
+            writeln!(file, "      \"name\": {},", json_string(row))?;
+            writeln!(file, "      \"cells\": [")?;
+            for column_index in 0..table.columns.len() {
+                let cell = table.cell(row_index, column_index);
+                let summary = cell.and_then(|cell| cell.summary());
+                let mut fields: Vec<String> = self
+                    .statistics
+                    .iter()
+                    .map(|statistic| {
+                        let value = summary.as_ref().map(|s: &Summary| s.get(*statistic));
+                        let value = value.map_or("null".to_string(), json_number);
+                        format!("{}: {value}", json_string(&statistic.name()))
+                    })
+                    .collect();
+                if self.raw {
+                    let samples: Vec<String> = cell
+                        .map(|cell| cell.samples().iter().map(|s| s.to_string()).collect())
+                        .unwrap_or_default();
+                    fields.push(format!("\"raw\": [{}]", samples.join(", ")));
+                }
+                let separator = if column_index + 1 < table.columns.len() {
+                    ","
+                } else {
+                    ""
+                };
+                writeln!(file, "        {{{}}}{separator}", fields.join(", "))?;
+            }
+            writeln!(file, "      ]")?;
+            let separator = if row_index + 1 < table.rows.len() {
+                ","
+            } else {
+                ""
+            };
+            writeln!(file, "    }}{separator}")?;
+        }
+        writeln!(file, "  ]")?;
+        writeln!(file, "}}")?;
+        file.flush()?;
+
+        Ok(vec![path])
+    }
+}
+
+/// A single `<file>.md` with the metadata and one table per statistic
+pub struct MarkdownExporter {
+    pub statistics: Vec<Statistic>,
+    pub directory: PathBuf,
+}
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+fn markdown_line(
+    file: &mut impl Write,
+    cells: impl IntoIterator<Item = String>,
+) -> std::io::Result<()> {
+    let cells: Vec<String> = cells.into_iter().map(|c| markdown_cell(&c)).collect();
+    writeln!(file, "| {} |", cells.join(" | "))
+}
+
+impl Exporter for MarkdownExporter {
+    fn export(&self, export: &Export) -> std::io::Result<Vec<PathBuf>> {
+        let table = export.table;
+        let file_name = format!("{}.md", export.file_name);
+        let (path, mut file) = create(&self.directory, &file_name)?;
+
+        writeln!(file, "## {}", markdown_cell(export.table_name))?;
+        writeln!(file)?;
+        for (key, value) in export.metadata {
+            writeln!(
+                file,
+                "- **{}**: {}",
+                markdown_cell(key),
+                markdown_cell(value)
+            )?;
+        }
+
+        for statistic in &self.statistics {
+            writeln!(file)?;
+            writeln!(file, "### {}", statistic.name())?;
+            writeln!(file)?;
+            let header = std::iter::once(String::new()).chain(table.columns.clone());
+            markdown_line(&mut file, header)?;
+            // names left aligned, numbers right aligned
+            let alignment = std::iter::once(":---".to_string())
+                .chain(table.columns.iter().map(|_| "---:".to_string()));
+            writeln!(file, "|{}|", alignment.collect::<Vec<_>>().join("|"))?;
+            for (row_name, row) in table.rows.iter().zip(table.get_statistic(*statistic)) {
+                let cells = row.iter().map(|cell| format!("{cell:.1}"));
+                markdown_line(&mut file, std::iter::once(row_name.clone()).chain(cells))?;
+            }
+        }
+        file.flush()?;
+
+        Ok(vec![path])
+    }
+}
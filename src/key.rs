@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+use crate::ball::Ball;
+use crate::experiment::ExperimentParameters;
+use crate::target::TargetShape;
+
+/// The value the balls are sorted by
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SortKey {
+    /// distance to the special ball's target shape, using the selected metric
+    Distance,
+    /// x coordinate
+    X,
+    /// y coordinate
+    Y,
+    /// length of the velocity
+    Speed,
+    /// direction of the velocity in radians, from -pi to pi
+    Heading,
+    Radius,
+}
+
+impl SortKey {
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
+
+pub fn ball_key(
+    exp_params: &ExperimentParameters,
+    transform: &Transform,
+    ball: &Ball,
+    special: (&Transform, &TargetShape),
+) -> f32 {
+    match exp_params.key {
+        SortKey::Distance => {
+            let (special_transform, shape) = special;
+            shape.distance(&exp_params.metric, special_transform.translation, transform.translation)
+        }
+        SortKey::X => transform.translation.x,
+        SortKey::Y => transform.translation.y,
+        SortKey::Speed => ball.velocity.length(),
+        SortKey::Heading => ball.velocity.y.atan2(ball.velocity.x),
+        SortKey::Radius => ball.radius,
+    }
+}
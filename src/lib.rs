@@ -0,0 +1,46 @@
+//! Merge sort implementations and the tools used to compare them.
+//!
+//! The sorting core in `algorithms` is `no_std` and only needs `alloc`.
+//! `Profiler` and the phase timer need the `std` feature.
+//! The `bevy` feature (on by default) adds the ECS adapters, `ProfilerPlugin` and `ExperimentPlugin`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod algorithms;
+#[cfg(feature = "std")]
+pub mod chart;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod profiler;
+#[cfg(feature = "std")]
+pub mod timing;
+
+#[cfg(feature = "bevy")]
+pub mod ball;
+#[cfg(feature = "bevy")]
+pub mod config;
+#[cfg(feature = "bevy")]
+pub mod dynamic;
+#[cfg(feature = "bevy")]
+pub mod experiment;
+#[cfg(feature = "bevy")]
+pub mod key;
+#[cfg(feature = "bevy")]
+pub mod metric;
+#[cfg(feature = "bevy")]
+pub mod random;
+#[cfg(feature = "bevy")]
+pub mod scenario;
+#[cfg(feature = "bevy")]
+pub mod sorting;
+#[cfg(feature = "bevy")]
+pub mod spawn;
+#[cfg(feature = "bevy")]
+pub mod target;
+#[cfg(feature = "bevy")]
+pub mod trace;
@@ -1,28 +1,53 @@
-mod algorithms;
-mod ball;
-mod experiment;
-mod profiler;
-
-use rand::prelude::*;
-use rand::rng;
-use std::f32::consts::PI;
+use std::path::PathBuf;
 use std::time::Duration;
 
-use bevy::color::palettes::basic::*;
-use bevy::math::ops::*;
+use bevy::input::common_conditions::input_just_pressed;
 use bevy::prelude::*;
-use clap::Parser;
-
-use crate::algorithms::{bottom_up, memory_efficient, top_down};
-use ball::*;
-use experiment::*;
-use profiler::*;
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+
+use iteration2::algorithms::SortOrder;
+use iteration2::ball::*;
+use iteration2::chart::*;
+use iteration2::compare::*;
+use iteration2::config::*;
+use iteration2::dynamic::*;
+use iteration2::experiment::*;
+use iteration2::export::*;
+use iteration2::key::*;
+use iteration2::metric::*;
+use iteration2::profiler::*;
+use iteration2::random::*;
+use iteration2::scenario::*;
+use iteration2::sorting::*;
+use iteration2::spawn::*;
+use iteration2::target::*;
+use iteration2::trace::*;
+use iteration2::timing::*;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Print step execution times and draw gizmos
-    #[arg(short = 'D', long, default_value_t = true)]
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// TOML experiment file, its keys are the long options of this command,
+    /// options given on the command line win over the file
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Sorting algorithms to run, by name, all of them if missing.
+    /// Names of algorithms from --sort-library can be used too
+    #[arg(long, value_delimiter = ',')]
+    algorithms: Vec<String>,
+    /// Print step execution times and draw gizmos, turn it off with --debug=false
+    #[arg(
+        short = 'D',
+        long,
+        default_value_t = true,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
     debug: bool,
     /// The number of nearest balls that should be marked
     #[arg(short, long, default_value_t = 20)]
@@ -30,254 +55,394 @@ struct Args {
     /// The starting step size
     #[arg(short, long, default_value_t = 50)]
     first: usize,
-    /// Step increment size
+    /// Step increment size for the arithmetic progression
     #[arg(short, long, default_value_t = 50)]
     step: usize,
     /// How many steps to run
     #[arg(short, long, default_value_t = 5)]
     number: usize,
+    /// How the sample size grows from step to step
+    #[arg(long, value_enum, default_value_t = Progression::Arithmetic)]
+    progression: Progression,
+    /// Factor between steps for the geometric progression
+    #[arg(long, default_value_t = 2.)]
+    ratio: f64,
+    /// Last sample size for the log progression
+    #[arg(long, default_value_t = 10_000)]
+    last: usize,
+    /// Explicit sample sizes, replaces the progression
+    #[arg(long, value_delimiter = ',')]
+    sizes: Vec<usize>,
     /// Maximum time for each step
     #[arg(short, long, default_value_t = 10.)]
     duration: f32,
     /// Min number of calculations that should be done for each step
     #[arg(short, long, default_value_t = 200)]
     min: usize,
+    /// Value the balls are sorted by
+    #[arg(long, value_enum, default_value_t = SortKey::Distance)]
+    key: SortKey,
+    /// Distance metric used to rank the balls
+    #[arg(long, value_enum, default_value_t = DistanceMetric::SquaredEuclidean)]
+    metric: DistanceMetric,
+    /// x and y weights for the weighted-euclidean metric
+    #[arg(long, allow_negative_numbers = true, value_delimiter = ',', num_args = 2, default_values_t = [1., 1.])]
+    weights: Vec<f32>,
+    /// Shape the balls are ranked against, centered on the special ball
+    #[arg(long, value_enum, default_value_t = TargetKind::Point)]
+    target: TargetKind,
+    /// Length of a segment target, or width and height of a rect target
+    #[arg(long, value_delimiter = ',', num_args = 2, default_values_t = [300., 200.])]
+    target_size: Vec<f32>,
+    /// Direction of a line or segment target in degrees
+    #[arg(long, default_value_t = 0.)]
+    target_angle: f32,
+    /// Count the growth of the reused temp buffers inside the timed region
+    #[arg(long, default_value_t = false)]
+    time_allocation: bool,
+    /// Which phases of the sorting system count towards sorting_times.csv,
+    /// every phase is also written to its own table
+    #[arg(long, value_enum, default_value_t = TimingScope::SortOnly)]
+    timing_scope: TimingScope,
+    /// Dynamic library with an extra sorting algorithm, can be repeated
+    #[arg(long = "sort-library", value_name = "PATH")]
+    sort_libraries: Vec<PathBuf>,
+    /// Order the balls are sorted in
+    #[arg(long, value_enum, default_value_t = SortOrder::Ascending)]
+    order: SortOrder,
+    /// Mark the farthest balls instead of the nearest
+    #[arg(long, default_value_t = false)]
+    farthest: bool,
+    /// Run without a window or renderer, the balls move in a virtual arena
+    #[arg(long, default_value_t = false)]
+    headless: bool,
+    /// Width and height of the arena when running headless
+    #[arg(long, value_delimiter = ',', num_args = 2, default_values_t = [1280., 720.])]
+    arena: Vec<f32>,
+    /// Seed for the ball layouts and the randomized block order,
+    /// a random one is picked and written to the metadata if missing
+    #[arg(long)]
+    seed: Option<u64>,
+    /// How the balls are placed when they are spawned
+    #[arg(long, value_enum, default_value_t = SpawnDistribution::Uniform)]
+    distribution: SpawnDistribution,
+    /// Number of clusters for the clusters and mixed distributions
+    #[arg(long, default_value_t = 4)]
+    clusters: usize,
+    /// Standard deviation of a cluster, or the width of a ring or line
+    #[arg(long, default_value_t = 40.)]
+    spread: f32,
+    /// Spawn the balls from a scenario file instead of generating them,
+    /// a sample size uses the first balls in the file
+    #[arg(long, value_name = "PATH")]
+    scenario: Option<PathBuf>,
+    /// Save the ball layout to a scenario file when the experiment ends,
+    /// or when S is pressed
+    #[arg(long, value_name = "PATH")]
+    save_scenario: Option<PathBuf>,
+    /// Record the ball positions of every frame to a binary trace,
+    /// the first block of each sample size is recorded
+    #[arg(long, value_name = "PATH")]
+    record_trace: Option<PathBuf>,
+    /// Replay the ball positions from a trace instead of moving the balls,
+    /// every variation sees the same frames
+    #[arg(long, value_name = "PATH", conflicts_with = "record_trace")]
+    replay_trace: Option<PathBuf>,
+    /// Order the (variation, sample size) blocks run in
+    #[arg(long, value_enum, default_value_t = BlockOrder::Sequential)]
+    block_order: BlockOrder,
+    /// Frames at the start of every step that are sorted but not measured
+    #[arg(long, default_value_t = 0)]
+    warmup_frames: usize,
+    /// Seconds at the start of every step that are sorted but not measured
+    #[arg(long, default_value_t = 0.)]
+    warmup_duration: f32,
+    /// Statistics written for every table, the mean goes to the plain file name
+    /// and every other statistic to its own file
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Statistic::Mean])]
+    stats: Vec<Statistic>,
+    /// Also write every single measurement, to a <table>_raw.csv file or into the json
+    #[arg(long, default_value_t = false)]
+    raw: bool,
+    /// Formats the tables are written in, can be given several times or comma separated
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Format::Csv])]
+    format: Vec<Format>,
+    /// Directory the tables are written to
+    #[arg(long, value_name = "PATH", default_value = OUTPUT_DIR)]
+    output_dir: PathBuf,
+    /// Statistic drawn as error bars in the svg chart, stddev or ci95 make sense
+    #[arg(long, value_enum)]
+    error_bars: Option<Statistic>,
+    /// Log scale for the sample sizes in the svg chart
+    #[arg(long, default_value_t = false)]
+    log_x: bool,
+    /// Log scale for the times in the svg chart
+    #[arg(long, default_value_t = false)]
+    log_y: bool,
 }
 
-fn main() {
-    let args = Args::parse();
-
-    App::new()
-        .insert_resource(ClearColor(Color::srgb(1., 1., 1.)))
-        .add_message::<MarkBallMessage>()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(ProfilerPlugin)
-        .add_plugins(ExperimentPlugin {
-            first: args.first,
-            step: args.step,
-            number_of_steps: args.number,
-            step_duration: Duration::from_secs_f32(args.duration),
-            min_calcs_per_step: args.min,
-            variations: 3,
-            pick_number: args.pick,
-            debug: args.debug,
-        })
-        .add_systems(Startup, (setup, add_ball))
-        .add_systems(
-            Update,
-            (
-                clear_balls
-                    .run_if(on_message::<ExperimentProgress>)
-                    .before(add_ball),
-                add_ball.run_if(on_message::<ExperimentProgress>),
-                move_balls,
-                sort_balls.after(move_balls),
-                color_marked_balls.after(sort_balls),
-            ),
-        )
-        .add_systems(
-            PostUpdate,
-            (
-                process_experiment_progress,
-                write_to_csvs
-                    .run_if(on_message::<AppExit>)
-                    .after(process_experiment_progress),
-            ),
-        )
-        .run();
-}
-
-#[derive(Resource)]
-struct SortingTableIndex(usize);
-
-fn setup(
-    mut commands: Commands,
-    mut profiler: ResMut<Profiler>,
-    exp_params: Res<ExperimentParameters>,
-) {
-    commands.spawn(Camera2d);
-
-    let index = profiler.create_table(
-        "Merge Sort implementations",
-        vec![
-            "MemoryEfficient".to_string(),
-            "TopDown".to_string(),
-            "BottomUp".to_string(),
-        ],
-        exp_params.relevant_samples().clone(),
-    );
-    commands.insert_resource(SortingTableIndex(index));
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two raw measurement files (written with --raw) and report the change of every cell,
+    /// exits with 1 if a significant regression is larger than the threshold and 2 on bad input
+    Compare {
+        /// The results to compare against
+        base: PathBuf,
+        /// The new results
+        new: PathBuf,
+        /// Largest allowed slowdown of a significant regression in percent
+        #[arg(long, default_value_t = 5.)]
+        threshold: f64,
+    },
 }
 
-fn clear_balls(balls: Query<Entity, With<Ball>>, mut commands: Commands) {
-    for ball in balls.iter() {
-        commands.entity(ball).despawn();
-    }
+/// Prints `error` as a usage error and exits
+fn invalid(error: String) -> ! {
+    Args::command().error(ErrorKind::ValueValidation, error).exit()
 }
 
-fn random_on_circle(rng: &mut ThreadRng) -> Vec2 {
-    let angle = rng.random::<f32>() * 2. * PI;
-    Vec2::new(cos(angle), sin(angle))
+/// Parses the command line, filling in options missing from it with the experiment file
+fn parse_args() -> Args {
+    parse_with_config(std::env::args_os().collect(), |args: &Args| args.config.as_ref())
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load experiment file {e}");
+            std::process::exit(1);
+        })
 }
 
-fn add_ball(
-    mut commands: Commands,
-    window: Single<&Window>,
-    exp_params: Res<ExperimentParameters>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let size = exp_params.current_sample_size();
-    let mut rng = rng();
-    let radius = 15.;
-    let min_speed = 50.;
-    let max_speed = 100.;
-    let height = window.height() - radius * 2.;
-    let width = window.width() - radius * 2.;
-    let miny = radius - window.height() / 2.;
-    let minx = radius - window.width() / 2.;
-
-    let mut balls: Vec<BallBundle> = Vec::with_capacity(size);
-
-    for _i in 0..size {
-        let random_x = minx + width * rng.random::<f32>();
-        let random_y = miny + height * rng.random::<f32>();
-        let random_speed = min_speed + (max_speed - min_speed) * rng.random::<f32>();
-        let random_velocity = random_on_circle(&mut rng) * random_speed;
-
-        balls.push(create_ball(
-            radius,
-            Color::from(GRAY),
-            Transform::from_xyz(random_x, random_y, 0.),
-            random_velocity,
-            // Vec2::ZERO,
-            &mut meshes,
-            &mut materials,
-        ));
+fn main() {
+    let args = parse_args();
+
+    if let Some(Command::Compare { base, new, threshold }) = &args.command {
+        // 1 for a regression over the threshold, 2 for bad input
+        match compare_files(base, new, *threshold) {
+            Ok(regressed) => std::process::exit(regressed as i32),
+            Err(e) => {
+                eprintln!("Failed to compare results {e}");
+                std::process::exit(2);
+            }
+        }
     }
 
-    commands.spawn_batch(balls);
-
-    let random_x = minx + width * rng.random::<f32>();
-    let random_y = miny + height * rng.random::<f32>();
-    let random_speed = min_speed + (max_speed - min_speed) * rng.random::<f32>();
-    let random_velocity = random_on_circle(&mut rng) * random_speed * 3.;
-
-    let special_ball = create_special_ball(
-        radius,
-        Color::from(BLACK),
-        Transform::from_xyz(random_x, random_y, 0.),
-        random_velocity,
-        &mut meshes,
-        &mut materials,
-    );
-
-    commands.spawn(special_ball);
-}
-
-fn check_in_bounds(rect: Rect, pos: Vec2, radius: f32) -> usize {
-    if pos.x > rect.max.x - radius {
-        return 1;
-    }
-    if pos.y > rect.max.y - radius {
-        return 2;
-    }
-    if pos.x < rect.min.x + radius {
-        return 3;
+    let dynamic_sorts = match DynamicSorts::load(&args.sort_libraries) {
+        Ok(sorts) => sorts,
+        Err(e) => {
+            eprintln!("Failed to load sort library {e}");
+            std::process::exit(1);
+        }
+    };
+    let mut names: Vec<String> = VARIATION_NAMES.iter().map(|name| name.to_string()).collect();
+    names.extend(dynamic_sorts.names());
+    let selected: Vec<usize> = if args.algorithms.is_empty() {
+        (0..names.len()).collect()
+    } else {
+        let find = |algorithm: &String| names.iter().position(|name| name.eq_ignore_ascii_case(algorithm));
+        args.algorithms
+            .iter()
+            .map(|algorithm| {
+                find(algorithm).unwrap_or_else(|| {
+                    eprintln!("Unknown algorithm {algorithm}, expected one of {}", names.join(", "));
+                    std::process::exit(1);
+                })
+            })
+            .collect()
+    };
+    let number_variations = selected.len();
+    let variations = Variations { names, selected };
+    let seed = args.seed.unwrap_or_else(rand::random);
+
+    let (sample_sizes, progression) = if args.sizes.is_empty() {
+        let sizes = generate_sample_sizes(
+            args.progression,
+            args.first,
+            args.step,
+            args.ratio,
+            args.last,
+            args.number,
+        )
+        .unwrap_or_else(|e| invalid(e));
+        let parameter = match args.progression {
+            Progression::Arithmetic => format!(" (step {})", args.step),
+            Progression::Geometric => format!(" (ratio {})", args.ratio),
+            _ => String::new(),
+        };
+        (sizes, format!("{}{parameter}", args.progression.name()))
+    } else {
+        check_sample_sizes(&args.sizes).unwrap_or_else(|e| invalid(e));
+        (args.sizes.clone(), "explicit".to_string())
+    };
+    if sample_sizes.is_empty() {
+        eprintln!("No sample sizes to run");
+        std::process::exit(1);
     }
-    if pos.y < rect.min.y + radius {
-        return 4;
+    let metric = Metric::new(args.metric, Vec2::new(args.weights[0], args.weights[1]))
+        .unwrap_or_else(|e| invalid(e));
+
+    let mut app = App::new();
+    if args.headless {
+        app.add_plugins(MinimalPlugins);
+    } else {
+        app.insert_resource(ClearColor(Color::srgb(1., 1., 1.)))
+            .add_plugins(DefaultPlugins)
+            .add_systems(Startup, spawn_camera)
+            .add_systems(PreStartup, fit_arena_to_window)
+            .add_systems(First, fit_arena_to_window)
+            .add_systems(
+                Update,
+                (
+                    color_marked_balls.after(sort_balls),
+                    draw_target.run_if(|exp_params: Res<ExperimentParameters>| exp_params.debug),
+                ),
+            );
     }
-    0
-}
-
-fn move_balls(balls: Query<(&mut Transform, &mut Ball)>, window: Single<&Window>, time: Res<Time>) {
-    for (mut transform, mut ball) in balls {
-        transform.translation += ball.velocity * time.delta_secs();
 
-        let window_rect = Rect::from_center_size(Vec2::ZERO, window.size());
-        let trunc_pos = transform.translation.truncate();
-
-        match check_in_bounds(window_rect, trunc_pos, ball.radius) {
-            1 => ball.velocity = ball.velocity.reflect(Vec3::NEG_X),
-            2 => ball.velocity = ball.velocity.reflect(Vec3::NEG_Y),
-            3 => ball.velocity = ball.velocity.reflect(Vec3::X),
-            4 => ball.velocity = ball.velocity.reflect(Vec3::Y),
-            _ => {}
-        }
+    app.add_message::<MarkBallMessage>()
+        .insert_resource(SeededRng::new(seed))
+        .insert_resource(SpawnSettings::new(args.distribution, args.clusters, args.spread))
+        .insert_resource(Arena(Vec2::new(args.arena[0], args.arena[1])))
+        .insert_resource(Output {
+            formats: args.format.clone(),
+            options: ExportOptions {
+                statistics: args.stats.clone(),
+                raw: args.raw,
+                chart: ChartOptions {
+                    statistic: args.stats.first().copied().unwrap_or(Statistic::Mean),
+                    error_bars: args.error_bars,
+                    log_x: args.log_x,
+                    log_y: args.log_y,
+                },
+                directory: args.output_dir.clone(),
+            },
+        })
+        .insert_resource(SortScratch::new(args.time_allocation))
+        .insert_resource(args.timing_scope)
+        .insert_resource(dynamic_sorts)
+        .insert_resource(variations)
+        .add_plugins(ProfilerPlugin)
+        .add_plugins(ExperimentPlugin {
+            sample_sizes,
+            step_duration: Duration::from_secs_f32(args.duration),
+            min_calcs_per_step: args.min,
+            warmup_frames: args.warmup_frames,
+            warmup_duration: Duration::from_secs_f32(args.warmup_duration),
+            variations: number_variations,
+            pick_number: args.pick,
+            key: args.key,
+            metric,
+            target: TargetShape::new(
+                args.target,
+                Vec2::new(args.target_size[0], args.target_size[1]),
+                args.target_angle,
+            ),
+            order: args.order,
+            farthest: args.farthest,
+            block_order: args.block_order,
+            seed,
+            debug: args.debug,
+        });
 
-        let r = Rect::from_center_half_size(
-            // window size adjusted for ball radius
-            window_rect.center(),
-            window_rect.half_size() - ball.radius,
-        );
-        let max_3 = Vec3::new(r.max.x, r.max.y, 0.);
-        let min_3 = Vec3::new(r.min.x, r.min.y, 0.);
+    app.world_mut().resource_mut::<Profiler>().add_metadata("progression", &progression);
 
-        transform.translation = transform.translation.clamp(min_3, max_3);
+    if let Some(path) = &args.config {
+        app.world_mut()
+            .resource_mut::<Profiler>()
+            .add_metadata("config", &path.display().to_string());
     }
-}
-
-fn sort_balls(
-    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
-    special: Single<&Transform, With<Special>>,
-    exp_params: Res<ExperimentParameters>,
-    writer: MessageWriter<MarkBallMessage>,
-    mut profiler: ResMut<Profiler>,
-    table_index: Res<SortingTableIndex>,
-) {
-    // todo: merge sort, memory efficient implementation
-    // todo: quick sort, random and resorting the last sorted list
-
-    let elapsed = match exp_params.variation_index {
-        1 => top_down(balls, special, &exp_params, writer),
-        2 => bottom_up(balls, special, &exp_params, writer),
-        _ => memory_efficient(balls, special, &exp_params, writer),
-    };
-
-    profiler.record_cell_data_by_table_row_col_index(
-        table_index.0,
-        exp_params.variation_index,
-        exp_params.sample_index,
-        elapsed,
-    );
-}
 
-#[derive(Message)]
-pub struct MarkBallMessage(Entity);
-
-fn color_marked_balls(
-    mut marked: MessageReader<MarkBallMessage>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    ball_materials: Query<&MeshMaterial2d<ColorMaterial>, Without<Special>>,
-) {
-    for ball_mat in ball_materials {
-        materials.get_mut(ball_mat).unwrap().color = Color::from(GRAY);
+    if let Some(path) = &args.scenario {
+        let scenario = match Scenario::read(path) {
+            Ok(scenario) => scenario,
+            Err(e) => {
+                eprintln!("Failed to load scenario {e}");
+                std::process::exit(1);
+            }
+        };
+        let exp_params = app.world().resource::<ExperimentParameters>();
+        let largest = exp_params.sample_sizes.iter().max();
+        if let Some(&largest) = largest.filter(|&&largest| largest > scenario.balls.len()) {
+            eprintln!(
+                "Scenario {} has {} balls but the largest sample size is {largest}",
+                path.display(),
+                scenario.balls.len()
+            );
+            std::process::exit(1);
+        }
+        app.world_mut()
+            .resource_mut::<Profiler>()
+            .add_metadata("scenario", &path.display().to_string());
+        app.insert_resource(scenario);
     }
 
-    for e in marked.read() {
-        if let Ok(mat) = ball_materials.get(e.0) {
-            materials.get_mut(mat).unwrap().color = Color::from(RED);
+    if let Some(path) = args.save_scenario {
+        app.insert_resource(ScenarioOutput(path)).add_systems(
+            PostUpdate,
+            save_scenario
+                .run_if(on_message::<AppExit>)
+                .after(process_experiment_progress),
+        );
+        if !args.headless {
+            app.add_systems(Update, save_scenario.run_if(input_just_pressed(KeyCode::KeyS)));
         }
     }
-}
 
-fn process_experiment_progress(
-    mut progress: MessageReader<ExperimentProgress>,
-    mut exit: MessageWriter<AppExit>,
-) {
-    for progress in progress.read() {
-        // let (prev_size, prev_var, last_sample) = (progress.0, progress.1, progress.2);
-        if progress.2 {
-            exit.write(AppExit::Success);
+    if let Some(path) = args.record_trace {
+        let exp_params = app.world().resource::<ExperimentParameters>();
+        let recorder = TraceRecorder::new(path.clone(), &exp_params.sample_sizes);
+        app.world_mut()
+            .resource_mut::<Profiler>()
+            .add_metadata("trace", &format!("recorded to {}", path.display()));
+        app.insert_resource(recorder)
+            .add_systems(Update, record_trace.after(move_balls).before(sort_balls))
+            .add_systems(
+                PostUpdate,
+                write_trace
+                    .run_if(on_message::<AppExit>)
+                    .after(process_experiment_progress),
+            );
+    }
+
+    if let Some(path) = &args.replay_trace {
+        let trace = match Trace::read(path) {
+            Ok(trace) => trace,
+            Err(e) => {
+                eprintln!("Failed to load trace {e}");
+                std::process::exit(1);
+            }
+        };
+        let exp_params = app.world().resource::<ExperimentParameters>();
+        if !trace.covers(&exp_params.sample_sizes) {
+            eprintln!(
+                "Trace {} was not recorded with the sample sizes {:?}",
+                path.display(),
+                exp_params.sample_sizes
+            );
+            std::process::exit(1);
         }
+        app.world_mut()
+            .resource_mut::<Profiler>()
+            .add_metadata("trace", &format!("replayed from {}", path.display()));
+        app.insert_resource(TraceReplay::new(trace))
+            .add_systems(Update, replay_trace.after(add_ball).before(sort_balls));
     }
-}
 
-fn write_to_csvs(profiler: Res<Profiler>, startup_instant: Res<StartupInstant>) {
-    profiler.write_to_csv("Merge Sort implementations", "sorting_times").unwrap();
-    let time = startup_instant.0.elapsed().as_secs();
-    let secs = time % 60;
-    let mins = time / 60;
-    println!("finished: {mins} minutes {secs} seconds");
+    app.add_systems(Startup, (setup_tables, add_ball))
+        .add_systems(
+            Update,
+            (
+                clear_balls
+                    .run_if(on_message::<ExperimentProgress>)
+                    .before(add_ball),
+                // the balls of the next block are spawned before anything moves or sorts them
+                add_ball.run_if(on_message::<ExperimentProgress>).before(move_balls),
+                move_balls.run_if(not(resource_exists::<TraceReplay>)),
+                sort_balls.after(move_balls),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            write_tables
+                .run_if(on_message::<AppExit>)
+                .after(process_experiment_progress),
+        )
+        .run();
 }
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum DistanceMetric {
+    /// (dx² + dy²), the default, avoids the square root
+    SquaredEuclidean,
+    /// |dx| + |dy|
+    Manhattan,
+    /// max(|dx|, |dy|)
+    Chebyshev,
+    /// (wx·dx² + wy·dy²), anisotropic version of squared euclidean
+    WeightedEuclidean,
+}
+
+impl DistanceMetric {
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Metric {
+    pub kind: DistanceMetric,
+    /// only used by `WeightedEuclidean`
+    pub weights: Vec2,
+}
+
+impl Metric {
+    /// negative weights would give negative distances, they are rejected for
+    /// `WeightedEuclidean` and ignored by the other metrics
+    pub fn new(kind: DistanceMetric, weights: Vec2) -> Result<Self, String> {
+        if kind == DistanceMetric::WeightedEuclidean
+            && !(weights.is_finite() && weights.cmpge(Vec2::ZERO).all() && weights != Vec2::ZERO)
+        {
+            return Err(format!(
+                "the weighted-euclidean metric needs weights of at least 0 and one above 0, \
+                 got {} and {}",
+                weights.x, weights.y
+            ));
+        }
+        Ok(Metric { kind, weights })
+    }
+
+    pub fn distance(&self, a: Vec3, b: Vec3) -> f32 {
+        let d = (a - b).truncate();
+        match self.kind {
+            DistanceMetric::SquaredEuclidean => d.length_squared(),
+            DistanceMetric::Manhattan => d.abs().element_sum(),
+            DistanceMetric::Chebyshev => d.abs().max_element(),
+            DistanceMetric::WeightedEuclidean => (self.weights * d * d).element_sum(),
+        }
+    }
+
+    /// description of the metric for output metadata
+    pub fn describe(&self) -> String {
+        match self.kind {
+            DistanceMetric::WeightedEuclidean => {
+                format!("{} ({} {})", self.kind.name(), self.weights.x, self.weights.y)
+            }
+            _ => self.kind.name(),
+        }
+    }
+}
@@ -1,9 +1,11 @@
+#[cfg(feature = "bevy")]
 use bevy::prelude::*;
-use std::fs::File;
-use std::io::Write;
+use crate::export::{Export, Exporter};
 
+#[cfg(feature = "bevy")]
 pub struct ProfilerPlugin;
 
+#[cfg(feature = "bevy")]
 impl Plugin for ProfilerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Profiler::new());
@@ -11,18 +13,22 @@ impl Plugin for ProfilerPlugin {
 }
 
 
-#[derive(Resource)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
+#[derive(Default)]
 pub struct Profiler {
     pub tables: Vec<Table>,
     pub table_names: Vec<String>,
+    /// key value pairs describing the run, written next to every table
+    pub metadata: Vec<(String, String)>,
 }
 
 impl Profiler {
-    fn new() -> Profiler {
-        Profiler {
-            tables: Vec::new(),
-            table_names: Vec::new(),
-        }
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    pub fn add_metadata(&mut self, key: &str, value: &str) {
+        self.metadata.push((key.to_string(), value.to_string()));
     }
 
     pub fn create_table(
@@ -65,66 +71,181 @@ impl Profiler {
         self.tables[table].insert_value_in_cell_by_indices(row, column, value);
     }
 
-    pub fn write_to_csv(&self, table: &str, file_name: &str) -> std::io::Result<()> {
+    /// writes a table with `exporter`, the file names are based on `file_name`
+    pub fn export(&self, table: &str, file_name: &str, exporter: &dyn Exporter) -> std::io::Result<()> {
         if let Some(table_ref) = self.get_table_ref(table) {
-            std::fs::create_dir_all("csv")?;
-            let mut file = File::create(format!("csv/{}.csv", file_name))?;
+            let written = exporter.export(&Export {
+                table_name: table,
+                table: table_ref,
+                metadata: &self.metadata,
+                file_name,
+            })?;
+            for file in written {
+                println!("Successful write to {}", file.display());
+            }
+        } else {
+            println!("Table with name {table} does not exist in profiler.")
+        }
+        Ok(())
+    }
+}
 
-            let averages = table_ref.get_averages();
+/// A statistic of the measurements in a cell that can be written out
+#[cfg_attr(feature = "bevy", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Statistic {
+    Mean,
+    Median,
+    Min,
+    Max,
+    /// sample standard deviation
+    Stddev,
+    P5,
+    P95,
+    P99,
+    /// half width of the 95% confidence interval of the mean
+    Ci95,
+    /// number of measurements
+    Count,
+}
 
-            file.write_all(table.as_bytes())?;
-            file.write_all(b",")?;
+impl Statistic {
+    pub fn name(&self) -> String {
+        match self {
+            Statistic::Mean => "mean".to_string(),
+            Statistic::Median => "median".to_string(),
+            Statistic::Min => "min".to_string(),
+            Statistic::Max => "max".to_string(),
+            Statistic::Stddev => "stddev".to_string(),
+            Statistic::P5 => "p5".to_string(),
+            Statistic::P95 => "p95".to_string(),
+            Statistic::P99 => "p99".to_string(),
+            Statistic::Ci95 => "ci95".to_string(),
+            Statistic::Count => "count".to_string(),
+        }
+    }
+}
 
-            // write all column names
-            for col in table_ref.columns.clone() {
-                let c = format!("{},", col);
-                file.write_all(c.as_bytes())?;
-            }
-            file.write_all(b"\n")?;
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    pub stddev: f64,
+    pub p5: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub ci95: f64,
+}
 
-            'rows: for (i, row) in averages.iter().enumerate() {
-                // write the row name first, break if no row names left
-                if i >= table_ref.rows.len() { break 'rows; }
-                file.write_all(format!("{},", table_ref.rows[i]).as_bytes())?;
+impl Summary {
+    pub fn new(samples: &[u128]) -> Option<Summary> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f64> = samples.iter().map(|s| *s as f64).collect();
+        sorted.sort_by(f64::total_cmp);
 
-                'cols: for (i, cell) in row.iter().enumerate() {
-                    // write only if there are column names left
-                    if i >= table_ref.columns.len() { break 'cols; }
-                    file.write_all(format!("{},", cell).as_bytes())?;
-                }
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / count;
+        let variance = sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1.).max(1.);
+        let stddev = variance.sqrt();
 
-                file.write_all(b"\n")?;
-            }
-            println!("Successful write to {file_name}.csv");
-        } else {
-            println!("Table with name {table} does not exist in profiler.")
+        Some(Summary {
+            count: sorted.len(),
+            mean,
+            median: percentile(&sorted, 50.),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            stddev,
+            p5: percentile(&sorted, 5.),
+            p95: percentile(&sorted, 95.),
+            p99: percentile(&sorted, 99.),
+            // normal approximation, good enough for the sample counts we collect
+            ci95: 1.96 * stddev / count.sqrt(),
+        })
+    }
+
+    pub fn get(&self, statistic: Statistic) -> f64 {
+        match statistic {
+            Statistic::Mean => self.mean,
+            Statistic::Median => self.median,
+            Statistic::Min => self.min,
+            Statistic::Max => self.max,
+            Statistic::Stddev => self.stddev,
+            Statistic::P5 => self.p5,
+            Statistic::P95 => self.p95,
+            Statistic::P99 => self.p99,
+            Statistic::Ci95 => self.ci95,
+            Statistic::Count => self.count as f64,
         }
-        Ok(())
     }
 }
 
-pub const COLUMNS: usize = 200;
-pub const ROWS: usize = 10;
+/// linear interpolation between the closest ranks of sorted samples
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = percent / 100. * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Every measurement recorded for one row and column
+#[derive(Clone, Default)]
+pub struct Cell {
+    samples: Vec<u128>,
+}
+
+impl Cell {
+    pub fn record(&mut self, value: u128) {
+        self.samples.push(value);
+    }
+
+    pub fn samples(&self) -> &[u128] {
+        &self.samples
+    }
+
+    pub fn summary(&self) -> Option<Summary> {
+        Summary::new(&self.samples)
+    }
+}
 
+/// Grows to fit any number of rows and columns, cells keep every measurement
 pub struct Table {
     pub columns: Vec<String>,
     pub rows: Vec<String>,
-    cells: [[[u128; 2]; COLUMNS]; ROWS],
+    /// indexed by row then column, rows can be shorter than `columns`
+    cells: Vec<Vec<Cell>>,
 }
 
 impl Table {
     fn new(columns: Vec<String>, rows: Vec<String>) -> Self {
-        Table {
-            columns,
-            rows,
-            cells: [[[0, 0]; COLUMNS]; ROWS],
-        }
+        let cells = vec![vec![Cell::default(); columns.len()]; rows.len()];
+        Table { columns, rows, cells }
     }
 
+    pub fn add_row(&mut self, row: &str) -> usize {
+        self.rows.push(row.to_string());
+        self.rows.len() - 1
+    }
+
+    pub fn add_column(&mut self, column: &str) -> usize {
+        self.columns.push(column.to_string());
+        self.columns.len() - 1
+    }
+
+    /// adds the row or column if the table does not have it yet
     #[allow(unused)]
     pub fn insert_value_in_cell(&mut self, row: &str, column: &str, value: u128) {
-        let row_index = self.rows.iter().position(|s| { s == row }).unwrap();
-        let column_index = self.columns.iter().position(|s| { s == column }).unwrap();
+        let row_index = match self.rows.iter().position(|s| { s == row }) {
+            Some(index) => index,
+            None => self.add_row(row),
+        };
+        let column_index = match self.columns.iter().position(|s| { s == column }) {
+            Some(index) => index,
+            None => self.add_column(column),
+        };
         self.insert_value_in_cell_by_indices(row_index, column_index, value);
     }
 
@@ -134,26 +255,36 @@ impl Table {
         column_index: usize,
         value: u128,
     ) {
-        let mut cell = self.cells[row_index][column_index];
-        cell[0] += 1; // increment number of times we have recorded data for this cell
-        cell[1] += value; // increase by the value of this recording, so we can average later
-        self.cells[row_index][column_index] = cell;
-    }
-
-    pub fn get_averages(&self) -> [[f64; COLUMNS]; ROWS] {
-        let mut averages: [[f64; COLUMNS]; ROWS] = [[0.; COLUMNS]; ROWS];
-
-        for (row, column) in self.cells.iter().enumerate() {
-            for (col, cell) in column.iter().enumerate() {
-                if cell[0] == 0 {
-                    averages[row][col] = 0.;
-                } else {
-                    averages[row][col] = cell[1] as f64 / cell[0] as f64;
-                }
-            }
+        if row_index >= self.cells.len() {
+            self.cells.resize_with(row_index + 1, Vec::new);
+        }
+        let row = &mut self.cells[row_index];
+        if column_index >= row.len() {
+            row.resize_with(column_index + 1, Cell::default);
         }
+        row[column_index].record(value);
+    }
 
-        averages
+    pub fn cell(&self, row_index: usize, column_index: usize) -> Option<&Cell> {
+        self.cells.get(row_index)?.get(column_index)
+    }
+
+    /// `statistic` of every named cell, 0 for cells without measurements
+    pub fn get_statistic(&self, statistic: Statistic) -> Vec<Vec<f64>> {
+        (0..self.rows.len())
+            .map(|row| {
+                (0..self.columns.len())
+                    .map(|col| {
+                        self.cell(row, col)
+                            .and_then(Cell::summary)
+                            .map_or(0., |summary| summary.get(statistic))
+                    })
+                    .collect()
+            })
+            .collect()
     }
-}
 
+    pub fn get_averages(&self) -> Vec<Vec<f64>> {
+        self.get_statistic(Statistic::Mean)
+    }
+}
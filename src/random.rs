@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Source of all randomness in the app, seeded so a run can be reproduced
+#[derive(Resource)]
+pub struct SeededRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng { seed, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// restarts the random stream at a point derived from the seed and `stream`,
+    /// so the same stream always produces the same values
+    pub fn reseed(&mut self, stream: u64) {
+        let mixed = self.seed ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        self.rng = StdRng::seed_from_u64(mixed);
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::ball::{Ball, Special};
+
+// A scenario is a plain text file with one ball per line:
+//
+//   # kind,radius,x,y,velocity x,velocity y
+//   special,15,-120.5,40,150.2,-80
+//   ball,15,300,-12.25,-60,40
+//
+// Blank lines and lines starting with `#` are ignored.
+
+#[derive(Clone, Copy, Debug)]
+pub struct BallState {
+    pub radius: f32,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+impl BallState {
+    fn line(&self, kind: &str) -> String {
+        format!(
+            "{kind},{},{},{},{},{}",
+            self.radius, self.position.x, self.position.y, self.velocity.x, self.velocity.y
+        )
+    }
+}
+
+/// A ball layout that can be written to a file and spawned again
+#[derive(Resource, Clone, Debug)]
+pub struct Scenario {
+    pub special: BallState,
+    pub balls: Vec<BallState>,
+}
+
+impl Scenario {
+    pub fn read(path: &Path) -> Result<Scenario, String> {
+        let file_error = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+        let error = |line: usize, e: &dyn std::fmt::Display| file_error(&format!("{line}: {e}"));
+        let text = fs::read_to_string(path).map_err(|e| file_error(&e))?;
+
+        let mut special = None;
+        let mut balls = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+            // split always returns at least one cell
+            let (kind, numbers) = (cells[0], &cells[1..]);
+            let numbers: Vec<f32> = numbers
+                .iter()
+                .map(|n| n.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| error(i + 1, &e))?;
+            let [radius, x, y, vx, vy] = numbers[..] else {
+                return Err(error(
+                    i + 1,
+                    &format!("expected 6 values but found {}", cells.len()),
+                ));
+            };
+
+            let ball = BallState {
+                radius,
+                position: Vec2::new(x, y),
+                velocity: Vec2::new(vx, vy),
+            };
+            match kind {
+                "ball" => balls.push(ball),
+                "special" if special.is_none() => special = Some(ball),
+                "special" => return Err(error(i + 1, &"more than one special ball")),
+                _ => return Err(error(i + 1, &format!("unknown kind {kind}"))),
+            }
+        }
+
+        let special = special.ok_or_else(|| file_error(&"no special ball"))?;
+        Ok(Scenario { special, balls })
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        writeln!(file, "# kind,radius,x,y,velocity x,velocity y")?;
+        writeln!(file, "{}", self.special.line("special"))?;
+        for ball in &self.balls {
+            writeln!(file, "{}", ball.line("ball"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Where `save_scenario` writes the current ball layout
+#[derive(Resource)]
+pub struct ScenarioOutput(pub PathBuf);
+
+pub fn save_scenario(
+    balls: Query<(&Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &Ball), With<Special>>,
+    output: Res<ScenarioOutput>,
+) {
+    let state = |(transform, ball): (&Transform, &Ball)| BallState {
+        radius: ball.radius,
+        position: transform.translation.truncate(),
+        velocity: ball.velocity.truncate(),
+    };
+    let scenario = Scenario {
+        special: state(*special),
+        balls: balls.iter().map(state).collect(),
+    };
+
+    match scenario.write(&output.0) {
+        Ok(()) => println!("Saved scenario to {}", output.0.display()),
+        Err(e) => eprintln!("Failed to save scenario {}: {e}", output.0.display()),
+    }
+}
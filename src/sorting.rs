@@ -0,0 +1,411 @@
+use crate::algorithms::*;
+use crate::ball::{Ball, MarkBallMessage, Special};
+use crate::dynamic::{DynamicSorts, dynamic};
+use crate::experiment::{ExperimentParameters, StartupInstant};
+use crate::export::{ExportOptions, Format};
+use crate::key::ball_key;
+use crate::profiler::Profiler;
+use crate::random::SeededRng;
+use crate::spawn::SpawnSettings;
+use crate::target::TargetShape;
+use crate::timing::{PhaseTimer, TimingScope};
+use bevy::prelude::*;
+
+/// Names of the sorting variations, in the order of `ExperimentParameters::variation_index`
+pub const VARIATION_NAMES: [&str; 5] = [
+    "MemoryEfficient",
+    "TopDown",
+    "BottomUp",
+    "MemoryEfficientSoA",
+    "BottomUpSoA",
+];
+
+/// Marks the `pick_number` nearest balls of a sorted sequence, or the farthest if requested.
+pub fn mark_picked(
+    sorted: impl DoubleEndedIterator<Item = Entity>,
+    exp_params: &ExperimentParameters,
+    writer: &mut MessageWriter<MarkBallMessage>,
+) {
+    // the wanted balls are at the back if the order and the ranking disagree
+    let from_back = (exp_params.order == SortOrder::Descending) != exp_params.farthest;
+
+    if from_back {
+        for entity in sorted.rev().take(exp_params.pick_number) {
+            writer.write(MarkBallMessage(entity));
+        }
+    } else {
+        for entity in sorted.take(exp_params.pick_number) {
+            writer.write(MarkBallMessage(entity));
+        }
+    }
+}
+
+const PLACEHOLDER: BallData<Entity> = BallData {
+    entity: Entity::PLACEHOLDER,
+    distance: 0.,
+};
+
+/// Temp buffers kept between frames, they only grow when the sample size increases.
+#[derive(Resource, Default)]
+pub struct SortScratch {
+    /// when true the buffer growth is measured as part of the sort
+    pub time_allocation: bool,
+    balls: Vec<BallData<Entity>>,
+    keys: Vec<f32>,
+    indices: Vec<u32>,
+    queues: QueuePool<BallData<Entity>>,
+    /// sort length the queue pool is warmed for
+    queue_length: usize,
+}
+
+impl SortScratch {
+    pub fn new(time_allocation: bool) -> Self {
+        SortScratch {
+            time_allocation,
+            ..default()
+        }
+    }
+
+    fn reserve(&mut self, length: usize) {
+        if self.balls.len() < length {
+            self.balls.resize(length, PLACEHOLDER);
+            self.keys.resize(length, 0.);
+            self.indices.resize(length, 0);
+        }
+    }
+
+    /// makes room for `length` elements, the growth only counts
+    /// towards the current phase if allocation should be timed
+    fn reserve_timed(&mut self, length: usize, timer: &mut PhaseTimer) {
+        if self.time_allocation {
+            self.reserve(length);
+        } else {
+            timer.exclude(|| self.reserve(length));
+        }
+    }
+
+    /// warms the queue pool whenever the length changes if allocation should not be timed,
+    /// otherwise `merge_top` grows the queues while it sorts
+    fn reserve_queues_timed(&mut self, length: usize, timer: &mut PhaseTimer) {
+        if self.time_allocation || self.queue_length == length {
+            return;
+        }
+        timer.exclude(|| self.queues.warm(length, PLACEHOLDER));
+        self.queue_length = length;
+    }
+}
+
+pub fn top_down(
+    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &TargetShape), With<Special>>,
+    exp_params: &Res<ExperimentParameters>,
+    scratch: &mut SortScratch,
+    timer: &mut PhaseTimer,
+    mut writer: MessageWriter<MarkBallMessage>,
+) {
+    // https://en.wikipedia.org/wiki/Merge_sort
+
+    scratch.reserve_queues_timed(exp_params.current_sample_size(), timer);
+    let mut unsorted_ball_list = scratch.queues.take(exp_params.current_sample_size());
+
+    for ball in balls {
+        unsorted_ball_list.push_back(BallData {
+            entity: ball.0,
+            distance: ball_key(exp_params, ball.1, ball.2, *special),
+        })
+    }
+
+    timer.end_gather();
+
+    let sorted_balls = merge_top(unsorted_ball_list, exp_params.order, &mut scratch.queues);
+    timer.end_sort();
+
+    mark_picked(sorted_balls.iter().map(|ball| ball.entity), exp_params, &mut writer);
+    scratch.queues.give(sorted_balls);
+}
+
+pub fn bottom_up(
+    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &TargetShape), With<Special>>,
+    exp_params: &Res<ExperimentParameters>,
+    scratch: &mut SortScratch,
+    timer: &mut PhaseTimer,
+    mut writer: MessageWriter<MarkBallMessage>,
+) {
+    // https://en.wikipedia.org/wiki/Merge_sort
+
+    let mut ball_list: Vec<BallData<Entity>> = Vec::with_capacity(exp_params.current_sample_size());
+
+    for ball in balls {
+        ball_list.push(BallData {
+            entity: ball.0,
+            distance: ball_key(exp_params, ball.1, ball.2, *special),
+        });
+    }
+
+    timer.end_gather();
+
+    let length = ball_list.len();
+    scratch.reserve_timed(length, timer);
+    merge_bottom(&mut ball_list, &mut scratch.balls[..length], exp_params.order);
+    timer.end_sort();
+
+    mark_picked(ball_list.iter().map(|ball| ball.entity), exp_params, &mut writer);
+}
+
+pub fn memory_efficient(
+    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &TargetShape), With<Special>>,
+    exp_params: &Res<ExperimentParameters>,
+    scratch: &mut SortScratch,
+    timer: &mut PhaseTimer,
+    mut writer: MessageWriter<MarkBallMessage>,
+) {
+    // https://www.geeksforgeeks.org/dsa/in-place-merge-sort/
+
+    let mut ball_list: Vec<BallData<Entity>> = Vec::with_capacity(exp_params.current_sample_size());
+
+    for ball in balls {
+        ball_list.push(BallData {
+            entity: ball.0,
+            distance: ball_key(exp_params, ball.1, ball.2, *special),
+        })
+    }
+
+    timer.end_gather();
+
+    let length = ball_list.len();
+    scratch.reserve_timed(length, timer);
+    merge_sort(&mut ball_list[..], &mut scratch.balls[..length], exp_params.order);
+    timer.end_sort();
+
+    mark_picked(ball_list.iter().map(|ball| ball.entity), exp_params, &mut writer);
+}
+
+/// Structure of arrays layout, the keys and the indices into `entities` are
+/// kept in separate arrays and permuted together, so merges compare packed keys.
+struct BallKeys {
+    entities: Vec<Entity>,
+    keys: Vec<f32>,
+    indices: Vec<u32>,
+}
+
+impl BallKeys {
+    fn gather(
+        balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+        special: Single<(&Transform, &TargetShape), With<Special>>,
+        exp_params: &Res<ExperimentParameters>,
+    ) -> BallKeys {
+        let capacity = exp_params.current_sample_size();
+
+        let mut ball_keys = BallKeys {
+            entities: Vec::with_capacity(capacity),
+            keys: Vec::with_capacity(capacity),
+            indices: Vec::with_capacity(capacity),
+        };
+
+        for (index, ball) in balls.iter().enumerate() {
+            ball_keys.entities.push(ball.0);
+            ball_keys.keys.push(ball_key(exp_params, ball.1, ball.2, *special));
+            ball_keys.indices.push(index as u32);
+        }
+
+        ball_keys
+    }
+
+    fn mark_first(
+        &self,
+        exp_params: &Res<ExperimentParameters>,
+        writer: &mut MessageWriter<MarkBallMessage>,
+    ) {
+        let sorted = self.indices.iter().map(|index| self.entities[*index as usize]);
+        mark_picked(sorted, exp_params, writer);
+    }
+}
+
+pub fn memory_efficient_soa(
+    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &TargetShape), With<Special>>,
+    exp_params: &Res<ExperimentParameters>,
+    scratch: &mut SortScratch,
+    timer: &mut PhaseTimer,
+    mut writer: MessageWriter<MarkBallMessage>,
+) {
+    let mut ball_keys = BallKeys::gather(balls, special, exp_params);
+    timer.end_gather();
+
+    let length = ball_keys.keys.len();
+    scratch.reserve_timed(length, timer);
+    merge_sort_soa(
+        &mut ball_keys.keys,
+        &mut ball_keys.indices,
+        &mut scratch.keys[..length],
+        &mut scratch.indices[..length],
+        exp_params.order,
+    );
+    timer.end_sort();
+
+    ball_keys.mark_first(exp_params, &mut writer);
+}
+
+pub fn bottom_up_soa(
+    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &TargetShape), With<Special>>,
+    exp_params: &Res<ExperimentParameters>,
+    scratch: &mut SortScratch,
+    timer: &mut PhaseTimer,
+    mut writer: MessageWriter<MarkBallMessage>,
+) {
+    let mut ball_keys = BallKeys::gather(balls, special, exp_params);
+    timer.end_gather();
+
+    let length = ball_keys.keys.len();
+    scratch.reserve_timed(length, timer);
+    merge_bottom_soa(
+        &mut ball_keys.keys,
+        &mut ball_keys.indices,
+        &mut scratch.keys[..length],
+        &mut scratch.indices[..length],
+        exp_params.order,
+    );
+    timer.end_sort();
+
+    ball_keys.mark_first(exp_params, &mut writer);
+}
+
+pub const SORTING_TABLE: (&str, &str) = ("Merge Sort implementations", "sorting_times");
+pub const PHASE_TABLES: [(&str, &str); 3] = [
+    ("Gather phase", "gather_times"),
+    ("Sort phase", "sort_times"),
+    ("Mark phase", "mark_times"),
+];
+
+/// Every algorithm that can run and the ones picked for this experiment,
+/// `ExperimentParameters::variation_index` indexes `selected`
+#[derive(Resource)]
+pub struct Variations {
+    /// the built in variations followed by the dynamic ones
+    pub names: Vec<String>,
+    pub selected: Vec<usize>,
+}
+
+#[derive(Resource)]
+pub struct SortingTables {
+    scoped: usize,
+    phases: [usize; 3],
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn setup_tables(
+    mut commands: Commands,
+    mut profiler: ResMut<Profiler>,
+    exp_params: Res<ExperimentParameters>,
+    scratch: Res<SortScratch>,
+    timing_scope: Res<TimingScope>,
+    variations: Res<Variations>,
+    seeded_rng: Res<SeededRng>,
+    spawn: Res<SpawnSettings>,
+) {
+    let variation_names: Vec<String> =
+        variations.selected.iter().map(|i| variations.names[*i].clone()).collect();
+    let scoped = profiler.create_table(
+        SORTING_TABLE.0,
+        variation_names.clone(),
+        exp_params.relevant_samples(),
+    );
+    let phases = PHASE_TABLES.map(|(table_name, _)| {
+        profiler.create_table(
+            table_name,
+            variation_names.clone(),
+            exp_params.relevant_samples(),
+        )
+    });
+    commands.insert_resource(SortingTables { scoped, phases });
+    profiler.add_metadata("key", &exp_params.key.name());
+    profiler.add_metadata("metric", &exp_params.metric.describe());
+    profiler.add_metadata("target", &exp_params.target.describe());
+    profiler.add_metadata("order", &exp_params.order.name());
+    profiler.add_metadata("marked", if exp_params.farthest { "farthest" } else { "nearest" });
+    profiler.add_metadata("time allocation", &scratch.time_allocation.to_string());
+    profiler.add_metadata("timing scope", &timing_scope.name());
+    profiler.add_metadata("seed", &seeded_rng.seed().to_string());
+    profiler.add_metadata("block order", &exp_params.block_order.name());
+    profiler.add_metadata(
+        "warmup",
+        &format!(
+            "{} frames, {} s",
+            exp_params.warmup_frames,
+            exp_params.warmup_duration.as_secs_f32()
+        ),
+    );
+    profiler.add_metadata("distribution", &spawn.describe());
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn sort_balls(
+    balls: Query<(Entity, &Transform, &Ball), Without<Special>>,
+    special: Single<(&Transform, &TargetShape), With<Special>>,
+    exp_params: Res<ExperimentParameters>,
+    writer: MessageWriter<MarkBallMessage>,
+    mut scratch: ResMut<SortScratch>,
+    mut profiler: ResMut<Profiler>,
+    tables: Res<SortingTables>,
+    timing_scope: Res<TimingScope>,
+    dynamic_sorts: Res<DynamicSorts>,
+    variations: Res<Variations>,
+) {
+    // todo: merge sort, memory efficient implementation
+    // todo: quick sort, random and resorting the last sorted list
+
+    let mut timer = PhaseTimer::start();
+    let t = &mut timer;
+
+    match variations.selected[exp_params.variation_index] {
+        i if i >= VARIATION_NAMES.len() => {
+            let sort = &dynamic_sorts.0[i - VARIATION_NAMES.len()];
+            dynamic(balls, special, &exp_params, sort, t, writer)
+        }
+        1 => top_down(balls, special, &exp_params, &mut scratch, t, writer),
+        2 => bottom_up(balls, special, &exp_params, &mut scratch, t, writer),
+        3 => memory_efficient_soa(balls, special, &exp_params, &mut scratch, t, writer),
+        4 => bottom_up_soa(balls, special, &exp_params, &mut scratch, t, writer),
+        _ => memory_efficient(balls, special, &exp_params, &mut scratch, t, writer),
+    };
+
+    let times = timer.finish();
+    if exp_params.warming_up() {
+        return;
+    }
+
+    let (row, column) = (exp_params.variation_index, exp_params.sample_index);
+    profiler.record_cell_data_by_table_row_col_index(
+        tables.scoped,
+        row,
+        column,
+        times.scoped(*timing_scope),
+    );
+    let [gather, sort, mark] = tables.phases;
+    profiler.record_cell_data_by_table_row_col_index(gather, row, column, times.gather);
+    profiler.record_cell_data_by_table_row_col_index(sort, row, column, times.sort);
+    profiler.record_cell_data_by_table_row_col_index(mark, row, column, times.mark);
+}
+
+/// What `write_tables` writes for every table
+#[derive(Resource)]
+pub struct Output {
+    pub formats: Vec<Format>,
+    pub options: ExportOptions,
+}
+
+pub fn write_tables(profiler: Res<Profiler>, output: Res<Output>, startup_instant: Res<StartupInstant>) {
+    for format in &output.formats {
+        let exporter = format.exporter(&output.options);
+        for (table_name, file_name) in std::iter::once(SORTING_TABLE).chain(PHASE_TABLES) {
+            profiler.export(table_name, file_name, exporter.as_ref()).unwrap();
+        }
+    }
+    let time = startup_instant.0.elapsed().as_secs();
+    let secs = time % 60;
+    let mins = time / 60;
+    println!("finished: {mins} minutes {secs} seconds");
+}
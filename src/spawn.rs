@@ -0,0 +1,159 @@
+use bevy::math::ops::*;
+use bevy::prelude::*;
+use clap::ValueEnum;
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// How the balls are placed when they are spawned
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SpawnDistribution {
+    /// evenly over the whole arena
+    Uniform,
+    /// gaussian clusters around random centers
+    Clusters,
+    /// on a ring around the special ball
+    Ring,
+    /// on a line through the center of the arena
+    Line,
+    /// on a regular grid, many balls share a distance
+    Grid,
+    /// an equal share of each of the other distributions
+    Mixed,
+}
+
+impl SpawnDistribution {
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SpawnSettings {
+    pub distribution: SpawnDistribution,
+    /// number of clusters for `Clusters`
+    pub clusters: usize,
+    /// standard deviation of a cluster, or the width of a ring or line
+    pub spread: f32,
+}
+
+impl SpawnSettings {
+    pub fn new(distribution: SpawnDistribution, clusters: usize, spread: f32) -> Self {
+        SpawnSettings {
+            distribution,
+            clusters: clusters.max(1),
+            spread,
+        }
+    }
+
+    /// description of the distribution for output metadata
+    pub fn describe(&self) -> String {
+        match self.distribution {
+            SpawnDistribution::Clusters | SpawnDistribution::Mixed => format!(
+                "{} ({} clusters, spread {})",
+                self.distribution.name(),
+                self.clusters,
+                self.spread
+            ),
+            SpawnDistribution::Ring | SpawnDistribution::Line => {
+                format!("{} (spread {})", self.distribution.name(), self.spread)
+            }
+            _ => self.distribution.name(),
+        }
+    }
+
+    /// `count` positions inside `area`, `special` is where the special ball starts
+    pub fn positions(
+        &self,
+        count: usize,
+        area: Rect,
+        special: Vec2,
+        rng: &mut impl Rng,
+    ) -> Vec<Vec2> {
+        let mut positions = Vec::with_capacity(count);
+        self.extend(self.distribution, count, area, special, rng, &mut positions);
+        positions
+    }
+
+    fn extend(
+        &self,
+        distribution: SpawnDistribution,
+        count: usize,
+        area: Rect,
+        special: Vec2,
+        rng: &mut impl Rng,
+        positions: &mut Vec<Vec2>,
+    ) {
+        match distribution {
+            SpawnDistribution::Uniform => {
+                positions.extend((0..count).map(|_| uniform(area, rng)));
+            }
+            SpawnDistribution::Clusters => {
+                let centers: Vec<Vec2> = (0..self.clusters).map(|_| uniform(area, rng)).collect();
+                positions.extend((0..count).map(|i| {
+                    let center = centers[i % centers.len()];
+                    inside(area, center + gaussian(rng) * self.spread)
+                }));
+            }
+            SpawnDistribution::Ring => {
+                let ring_radius = area.size().min_element() / 3.;
+                positions.extend((0..count).map(|_| {
+                    let offset = (rng.random::<f32>() - 0.5) * self.spread;
+                    let angle = rng.random::<f32>() * 2. * PI;
+                    let direction = Vec2::new(cos(angle), sin(angle));
+                    inside(area, special + direction * (ring_radius + offset))
+                }));
+            }
+            SpawnDistribution::Line => {
+                let angle = rng.random::<f32>() * PI;
+                let direction = Vec2::new(cos(angle), sin(angle));
+                let normal = direction.perp();
+                let half_length = area.half_size().length();
+                positions.extend((0..count).map(|_| {
+                    let along = (rng.random::<f32>() * 2. - 1.) * half_length;
+                    let across = (rng.random::<f32>() - 0.5) * self.spread;
+                    inside(area, area.center() + direction * along + normal * across)
+                }));
+            }
+            SpawnDistribution::Grid => {
+                let columns = (count as f32).sqrt().ceil().max(1.) as usize;
+                let rows = count.div_ceil(columns).max(1);
+                let cell = area.size() / Vec2::new(columns as f32, rows as f32);
+                positions.extend((0..count).map(|i| {
+                    let index = Vec2::new((i % columns) as f32, (i / columns) as f32);
+                    area.min + cell * (index + 0.5)
+                }));
+            }
+            SpawnDistribution::Mixed => {
+                let parts = [
+                    SpawnDistribution::Uniform,
+                    SpawnDistribution::Clusters,
+                    SpawnDistribution::Ring,
+                    SpawnDistribution::Line,
+                    SpawnDistribution::Grid,
+                ];
+                for (i, part) in parts.into_iter().enumerate() {
+                    // the first parts take the remainder
+                    let share = count / parts.len() + usize::from(i < count % parts.len());
+                    self.extend(part, share, area, special, rng, positions);
+                }
+            }
+        }
+    }
+}
+
+fn inside(area: Rect, point: Vec2) -> Vec2 {
+    point.clamp(area.min, area.max)
+}
+
+fn uniform(area: Rect, rng: &mut impl Rng) -> Vec2 {
+    area.min + area.size() * Vec2::new(rng.random(), rng.random())
+}
+
+/// standard normal sample in two dimensions, Box-Muller transform
+fn gaussian(rng: &mut impl Rng) -> Vec2 {
+    let u1 = rng.random::<f32>().max(f32::MIN_POSITIVE);
+    let u2 = rng.random::<f32>();
+    let r = sqrt(-2. * ln(u1));
+    let angle = 2. * PI * u2;
+    Vec2::new(r * cos(angle), r * sin(angle))
+}
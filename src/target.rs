@@ -0,0 +1,105 @@
+use bevy::color::palettes::basic::BLACK;
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+use crate::ball::Special;
+use crate::metric::Metric;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum TargetKind {
+    /// the special ball's position
+    Point,
+    /// infinite line through the special ball
+    Line,
+    /// line segment centered on the special ball
+    Segment,
+    /// axis aligned rectangle centered on the special ball
+    Rect,
+}
+
+/// The shape the balls are ranked against, it follows the special ball around.
+#[derive(Component, Clone, Copy, Debug)]
+pub enum TargetShape {
+    Point,
+    Line { direction: Vec2 },
+    Segment { half_extent: Vec2 },
+    Rect { half_size: Vec2 },
+}
+
+impl TargetShape {
+    /// `size` is the length of a segment or the width and height of a rect,
+    /// `angle` (degrees) is the direction of a line or segment
+    pub fn new(kind: TargetKind, size: Vec2, angle: f32) -> Self {
+        let direction = Vec2::from_angle(angle.to_radians());
+        match kind {
+            TargetKind::Point => TargetShape::Point,
+            TargetKind::Line => TargetShape::Line { direction },
+            TargetKind::Segment => TargetShape::Segment { half_extent: direction * size.x / 2. },
+            TargetKind::Rect => TargetShape::Rect { half_size: size / 2. },
+        }
+    }
+
+    /// closest point on the shape centered at `center` to `pos`
+    pub fn closest_point(&self, center: Vec2, pos: Vec2) -> Vec2 {
+        match *self {
+            TargetShape::Point => center,
+            TargetShape::Line { direction } => center + (pos - center).project_onto_normalized(direction),
+            TargetShape::Segment { half_extent } => {
+                let length_squared = half_extent.length_squared();
+                if length_squared == 0. {
+                    return center;
+                }
+                let t = (pos - center).dot(half_extent) / length_squared;
+                center + half_extent * t.clamp(-1., 1.)
+            }
+            // points inside the rect are at distance zero
+            TargetShape::Rect { half_size } => pos.clamp(center - half_size, center + half_size),
+        }
+    }
+
+    pub fn distance(&self, metric: &Metric, center: Vec3, pos: Vec3) -> f32 {
+        let closest = self.closest_point(center.truncate(), pos.truncate());
+        metric.distance(pos, closest.extend(pos.z))
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            TargetShape::Point => "point".to_string(),
+            TargetShape::Line { direction } => {
+                format!("line ({} degrees)", direction.to_angle().to_degrees())
+            }
+            // a zero length segment has no direction
+            TargetShape::Segment { half_extent } if half_extent == Vec2::ZERO => "point".to_string(),
+            TargetShape::Segment { half_extent } => format!(
+                "segment ({} long {} degrees)",
+                half_extent.length() * 2.,
+                half_extent.to_angle().to_degrees()
+            ),
+            TargetShape::Rect { half_size } => {
+                format!("rect ({} x {})", half_size.x * 2., half_size.y * 2.)
+            }
+        }
+    }
+
+    pub fn draw(&self, gizmos: &mut Gizmos, center: Vec2, color: Color) {
+        match *self {
+            TargetShape::Point => {}
+            TargetShape::Line { direction } => {
+                // far enough to leave any window
+                let far = direction * 10000.;
+                gizmos.line_2d(center - far, center + far, color);
+            }
+            TargetShape::Segment { half_extent } => {
+                gizmos.line_2d(center - half_extent, center + half_extent, color);
+            }
+            TargetShape::Rect { half_size } => {
+                gizmos.rect_2d(Isometry2d::from_translation(center), half_size * 2., color);
+            }
+        }
+    }
+}
+
+pub fn draw_target(special: Single<(&Transform, &TargetShape), With<Special>>, mut gizmos: Gizmos) {
+    let (transform, shape) = *special;
+    shape.draw(&mut gizmos, transform.translation.truncate(), Color::from(BLACK));
+}
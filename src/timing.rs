@@ -0,0 +1,89 @@
+#[cfg(feature = "bevy")]
+use bevy::prelude::*;
+use std::time::{Duration, Instant};
+
+/// Which phases of `sort_balls` count towards the main sorting table
+#[cfg_attr(feature = "bevy", derive(Resource, clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimingScope {
+    /// only the merge sort itself
+    SortOnly,
+    /// collecting the balls and their distances plus the sort
+    GatherSort,
+    /// the whole system, including marking the picked balls
+    Full,
+}
+
+impl TimingScope {
+    pub fn name(&self) -> String {
+        match self {
+            TimingScope::SortOnly => "sort-only".to_string(),
+            TimingScope::GatherSort => "gather-sort".to_string(),
+            TimingScope::Full => "full".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PhaseTimes {
+    pub gather: u128,
+    pub sort: u128,
+    pub mark: u128,
+}
+
+impl PhaseTimes {
+    pub fn scoped(&self, scope: TimingScope) -> u128 {
+        match scope {
+            TimingScope::SortOnly => self.sort,
+            TimingScope::GatherSort => self.gather + self.sort,
+            TimingScope::Full => self.gather + self.sort + self.mark,
+        }
+    }
+}
+
+/// Splits one run of a sorting variation into gather, sort and mark phases.
+/// Every variation calls `end_gather` and `end_sort`, the mark phase lasts until `finish`.
+pub struct PhaseTimer {
+    last: Instant,
+    excluded: Duration,
+    times: PhaseTimes,
+}
+
+impl PhaseTimer {
+    pub fn start() -> Self {
+        PhaseTimer {
+            last: Instant::now(),
+            excluded: Duration::ZERO,
+            times: PhaseTimes::default(),
+        }
+    }
+
+    fn lap(&mut self) -> u128 {
+        let now = Instant::now();
+        let elapsed = (now - self.last).saturating_sub(self.excluded);
+        self.last = now;
+        self.excluded = Duration::ZERO;
+        elapsed.as_nanos()
+    }
+
+    pub fn end_gather(&mut self) {
+        self.times.gather = self.lap();
+    }
+
+    pub fn end_sort(&mut self) {
+        self.times.sort = self.lap();
+    }
+
+    pub fn finish(mut self) -> PhaseTimes {
+        self.times.mark = self.lap();
+        self.times
+    }
+
+    /// runs `f` without counting its time towards the current phase
+    pub fn exclude<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let result = f();
+        self.excluded += start.elapsed();
+        result
+    }
+}
@@ -0,0 +1,266 @@
+use bevy::prelude::*;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::ball::{Ball, Special};
+use crate::experiment::ExperimentParameters;
+
+// Binary trace of the ball positions, little endian:
+//
+//   b"BTRC", version: u32, sample count: u32
+//   per sample size: balls: u32, frames: u32, then per frame
+//   the special ball followed by every other ball as x, y, velocity x, velocity y: f32
+
+const MAGIC: &[u8; 4] = b"BTRC";
+const VERSION: u32 = 1;
+/// floats stored per ball and frame
+const STRIDE: usize = 4;
+
+/// Order of a ball in the spawned layout, keeps traces independent of query order
+#[derive(Component, Clone, Copy)]
+pub struct LayoutIndex(pub usize);
+
+/// Every recorded frame for one sample size
+#[derive(Clone, Debug, Default)]
+pub struct SampleTrace {
+    /// balls without the special ball
+    pub balls: usize,
+    values: Vec<f32>,
+}
+
+impl SampleTrace {
+    pub fn new(balls: usize) -> Self {
+        SampleTrace {
+            balls,
+            values: Vec::new(),
+        }
+    }
+
+    fn frame_len(&self) -> usize {
+        (self.balls + 1) * STRIDE
+    }
+
+    pub fn frames(&self) -> usize {
+        self.values.len() / self.frame_len()
+    }
+
+    /// `frame` holds the special ball first, then the balls in layout order
+    pub fn push_frame(&mut self, frame: &[(Vec2, Vec2)]) {
+        debug_assert_eq!(frame.len(), self.balls + 1);
+        for (position, velocity) in frame {
+            self.values
+                .extend([position.x, position.y, velocity.x, velocity.y]);
+        }
+    }
+
+    /// position and velocity of ball `ball` in frame `frame`, ball 0 is the special ball
+    pub fn get(&self, frame: usize, ball: usize) -> (Vec2, Vec2) {
+        let start = frame * self.frame_len() + ball * STRIDE;
+        let v = &self.values[start..start + STRIDE];
+        (Vec2::new(v[0], v[1]), Vec2::new(v[2], v[3]))
+    }
+}
+
+/// Ball positions for every frame of an experiment, one entry per sample size
+#[derive(Clone, Debug, Default)]
+pub struct Trace {
+    pub samples: Vec<SampleTrace>,
+}
+
+impl Trace {
+    pub fn read(path: &Path) -> Result<Trace, String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+        let bytes = fs::read(path).map_err(|e| error(&e))?;
+
+        let mut bytes = Bytes(&bytes);
+        let eof = || error(&"unexpected end of file");
+
+        if bytes.take(4).ok_or_else(eof)? != MAGIC {
+            return Err(error(&"not a trace file"));
+        }
+        let version = bytes.u32().ok_or_else(eof)?;
+        if version != VERSION {
+            return Err(error(&format!(
+                "trace version {version} but expected {VERSION}"
+            )));
+        }
+
+        let mut samples = Vec::new();
+        for i in 0..bytes.u32().ok_or_else(eof)? {
+            let balls = bytes.u32().ok_or_else(eof)? as usize;
+            let frames = bytes.u32().ok_or_else(eof)? as usize;
+            // the header is not trusted, a broken one must not overflow
+            let floats = balls
+                .checked_add(1)
+                .and_then(|balls| balls.checked_mul(STRIDE))
+                .and_then(|frame_len| frame_len.checked_mul(frames))
+                .filter(|floats| floats.checked_mul(4).is_some())
+                .ok_or_else(|| error(&format!("sample {i} is too large")))?;
+            let values = bytes.take(floats * 4).ok_or_else(eof)?;
+
+            let mut sample = SampleTrace::new(balls);
+            sample.values = values
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            if sample.values.len() != floats {
+                return Err(error(&format!(
+                    "sample {i} has {} values but expected {floats}",
+                    sample.values.len()
+                )));
+            }
+            samples.push(sample);
+        }
+        if !bytes.0.is_empty() {
+            return Err(error(&format!(
+                "{} bytes after the last sample",
+                bytes.0.len()
+            )));
+        }
+
+        Ok(Trace { samples })
+    }
+
+    /// true if every sample size has a recording with that many balls
+    pub fn covers(&self, sample_sizes: &[usize]) -> bool {
+        sample_sizes.len() <= self.samples.len()
+            && sample_sizes
+                .iter()
+                .zip(&self.samples)
+                .all(|(&size, sample)| sample.balls == size && sample.frames() > 0)
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.write_all(&(self.samples.len() as u32).to_le_bytes())?;
+        for sample in &self.samples {
+            file.write_all(&(sample.balls as u32).to_le_bytes())?;
+            file.write_all(&(sample.frames() as u32).to_le_bytes())?;
+            for value in &sample.values {
+                file.write_all(&value.to_le_bytes())?;
+            }
+        }
+        file.flush()
+    }
+}
+
+#[derive(Resource)]
+pub struct TraceRecorder {
+    path: PathBuf,
+    trace: Trace,
+    /// the variation whose frames are recorded for each sample size
+    recorded_by: Vec<Option<usize>>,
+}
+
+impl TraceRecorder {
+    pub fn new(path: PathBuf, sample_sizes: &[usize]) -> Self {
+        TraceRecorder {
+            path,
+            trace: Trace {
+                samples: sample_sizes.iter().map(|&size| SampleTrace::new(size)).collect(),
+            },
+            recorded_by: vec![None; sample_sizes.len()],
+        }
+    }
+}
+
+pub fn record_trace(
+    balls: Query<(&Transform, &Ball, &LayoutIndex), Without<Special>>,
+    special: Single<(&Transform, &Ball), With<Special>>,
+    exp_params: Res<ExperimentParameters>,
+    mut recorder: ResMut<TraceRecorder>,
+) {
+    let (variation, sample) = (exp_params.variation_index, exp_params.sample_index);
+    let recorded_by = recorder.recorded_by[sample].get_or_insert(variation);
+    if *recorded_by != variation {
+        return;
+    }
+    // the balls of the previous block can still be around for a frame
+    if balls.iter().len() != exp_params.current_sample_size() {
+        return;
+    }
+
+    let state = |transform: &Transform, ball: &Ball| {
+        (transform.translation.truncate(), ball.velocity.truncate())
+    };
+    let mut frame = vec![(Vec2::ZERO, Vec2::ZERO); balls.iter().len() + 1];
+    frame[0] = state(special.0, special.1);
+    for (transform, ball, index) in balls {
+        frame[index.0 + 1] = state(transform, ball);
+    }
+    recorder.trace.samples[sample].push_frame(&frame);
+}
+
+pub fn write_trace(recorder: Res<TraceRecorder>) {
+    match recorder.trace.write(&recorder.path) {
+        Ok(()) => println!("Saved trace to {}", recorder.path.display()),
+        Err(e) => eprintln!("Failed to save trace {}: {e}", recorder.path.display()),
+    }
+}
+
+#[derive(Resource)]
+pub struct TraceReplay {
+    trace: Trace,
+    /// (variation, sample) the frame count belongs to
+    block: (usize, usize),
+    frame: usize,
+}
+
+impl TraceReplay {
+    pub fn new(trace: Trace) -> Self {
+        TraceReplay {
+            trace,
+            block: (usize::MAX, usize::MAX),
+            frame: 0,
+        }
+    }
+}
+
+pub fn replay_trace(
+    balls: Query<(&mut Transform, &mut Ball, &LayoutIndex), Without<Special>>,
+    special: Single<(&mut Transform, &mut Ball), With<Special>>,
+    exp_params: Res<ExperimentParameters>,
+    mut replay: ResMut<TraceReplay>,
+) {
+    let block = (exp_params.variation_index, exp_params.sample_index);
+    if replay.block != block {
+        replay.block = block;
+        replay.frame = 0;
+    }
+
+    let sample = &replay.trace.samples[exp_params.sample_index];
+    if balls.iter().len() != sample.balls {
+        return;
+    }
+    // blocks running longer than the recording start over
+    let frame = replay.frame % sample.frames();
+
+    let apply = |transform: &mut Transform, ball: &mut Ball, (position, velocity): (Vec2, Vec2)| {
+        transform.translation = position.extend(transform.translation.z);
+        ball.velocity = velocity.extend(0.);
+    };
+    let (mut special_transform, mut special_ball) = special.into_inner();
+    apply(&mut special_transform, &mut special_ball, sample.get(frame, 0));
+    for (mut transform, mut ball, index) in balls {
+        apply(&mut transform, &mut ball, sample.get(frame, index.0 + 1));
+    }
+    replay.frame += 1;
+}
+
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let (taken, rest) = self.0.split_at_checked(n)?;
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+}
@@ -0,0 +1,152 @@
+//! Draws small tables as svg charts and checks the markup and what ends up on the plot.
+
+#![cfg(feature = "std")]
+
+use iteration2::chart::{ChartExporter, ChartOptions};
+use iteration2::export::{Export, Exporter};
+use iteration2::profiler::{Profiler, Statistic};
+
+mod common;
+use common::TempDir;
+
+/// the svg text of a chart
+fn chart(profiler: &Profiler, name: &str, options: ChartOptions) -> String {
+    let directory = TempDir::new(&format!("chart_{name}"));
+    let written = ChartExporter {
+        options,
+        directory: directory.0.clone(),
+    }
+    .export(&Export {
+        table_name: &profiler.table_names[0],
+        table: &profiler.tables[0],
+        metadata: &profiler.metadata,
+        file_name: "sorts",
+    })
+    .unwrap();
+    assert_eq!(written, [directory.0.join("sorts.svg")]);
+
+    std::fs::read_to_string(&written[0]).unwrap()
+}
+
+fn options(error_bars: Option<Statistic>, log: bool) -> ChartOptions {
+    ChartOptions {
+        statistic: Statistic::Mean,
+        error_bars,
+        log_x: log,
+        log_y: log,
+    }
+}
+
+/// a row with three measurements per size and one with a missing cell
+fn profiler(columns: &[&str]) -> Profiler {
+    let mut profiler = Profiler::new();
+    let table = profiler.create_table(
+        "Sorts & <friends>",
+        vec!["Merge <a>".to_string(), "Quick".to_string()],
+        columns.iter().map(|c| c.to_string()).collect(),
+    );
+    for column in 0..columns.len() {
+        let base = 1000 * 10u128.pow(column as u32);
+        for value in [base, base * 2, base * 3] {
+            profiler.record_cell_data_by_table_row_col_index(table, 0, column, value);
+        }
+        if column != 1 {
+            profiler.record_cell_data_by_table_row_col_index(table, 1, column, base);
+        }
+    }
+    profiler
+}
+
+/// names of the elements in `svg`, panics if tags are not closed in order
+fn elements(svg: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut open = Vec::new();
+    for tag in svg.split('<').skip(1) {
+        let tag = &tag[..tag.find('>').expect("unterminated tag")];
+        if let Some(name) = tag.strip_prefix('/') {
+            assert_eq!(open.pop(), Some(name), "closing tag out of order");
+            continue;
+        }
+        let name = tag.split_whitespace().next().unwrap();
+        names.push(name);
+        if !tag.ends_with('/') {
+            open.push(name);
+        }
+    }
+    assert!(open.is_empty(), "unclosed elements {open:?}");
+    names
+}
+
+fn count(names: &[&str], name: &str) -> usize {
+    names.iter().filter(|n| **n == name).count()
+}
+
+#[test]
+fn chart_is_well_formed_with_a_line_per_row() {
+    let profiler = profiler(&["10", "100", "1000"]);
+
+    let svg = chart(&profiler, "plain", options(None, false));
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    let names = elements(&svg);
+    assert_eq!(count(&names, "svg"), 1);
+    assert_eq!(count(&names, "polyline"), 2);
+    // one point per cell with measurements
+    assert_eq!(count(&names, "circle"), 5);
+    assert_eq!(count(&names, "path"), 0);
+    assert!(!svg.contains("error bars"));
+
+    // names are escaped, the largest mean is 200µs
+    assert!(svg.contains(">Sorts &amp; &lt;friends&gt;</text>"));
+    assert!(svg.contains(">Merge &lt;a&gt;</text>"));
+    assert!(svg.contains(">sample size (balls)</text>"));
+    assert!(svg.contains(">mean time (µs)</text>"));
+}
+
+#[test]
+fn error_bars_are_drawn_where_measurements_vary() {
+    let profiler = profiler(&["10", "100", "1000"]);
+
+    let svg = chart(
+        &profiler,
+        "error_bars",
+        options(Some(Statistic::Stddev), false),
+    );
+
+    let names = elements(&svg);
+    // Quick has a single measurement per cell, so no spread
+    assert_eq!(count(&names, "path"), 3);
+    assert!(svg.contains(">error bars: ± stddev</text>"));
+}
+
+#[test]
+fn log_axes_have_decade_ticks_and_hide_non_positive_sizes() {
+    let profiler = profiler(&["0", "10", "100"]);
+
+    let svg = chart(&profiler, "log", options(Some(Statistic::Stddev), true));
+
+    let names = elements(&svg);
+    // the two points at size 0 can not be placed on a log axis
+    assert_eq!(count(&names, "circle"), 3);
+    assert_eq!(count(&names, "path"), 2);
+    for tick in ["10", "100"] {
+        assert!(svg.contains(&format!(">{tick}</text>")), "tick {tick}");
+    }
+    assert!(svg.contains(">sample size (balls, log scale)</text>"));
+    assert!(svg.contains(">mean time (µs, log scale)</text>"));
+}
+
+#[test]
+fn counts_have_no_time_unit() {
+    let profiler = profiler(&["10", "100"]);
+    let options = ChartOptions {
+        statistic: Statistic::Count,
+        ..options(None, false)
+    };
+
+    let svg = chart(&profiler, "count", options);
+
+    elements(&svg);
+    assert!(svg.contains(">count</text>"));
+}
@@ -0,0 +1,46 @@
+//! Helpers shared by the integration tests, every test file only uses some of them.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+/// a file in the temp dir that is removed again when dropped,
+/// the process id keeps parallel test runs apart
+pub struct TempFile(pub PathBuf);
+
+impl TempFile {
+    pub fn new(name: &str) -> Self {
+        TempFile(std::env::temp_dir().join(format!("iteration2_{}_{name}", std::process::id())))
+    }
+
+    pub fn with_bytes(name: &str, bytes: &[u8]) -> Self {
+        let file = TempFile::new(name);
+        std::fs::write(&file.0, bytes).unwrap();
+        file
+    }
+
+    pub fn with_text(name: &str, text: &str) -> Self {
+        TempFile::with_bytes(name, text.as_bytes())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// a directory in the temp dir that is removed with everything in it when dropped
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        TempDir(std::env::temp_dir().join(format!("iteration2_{}_{name}", std::process::id())))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,215 @@
+//! Reads raw measurement files and checks the verdicts of the comparison.
+
+#![cfg(feature = "std")]
+
+use iteration2::compare::*;
+
+mod common;
+use common::TempFile;
+
+/// a raw csv with one cell per entry of `cells`, quoted like the csv exporter does
+fn raw_csv(cells: &[(&str, &str, &[u128])]) -> String {
+    let quote = |field: &str| format!("\"{}\"", field.replace('"', "\"\""));
+    let mut text = "\"row\",\"column\",\"value\"\n".to_string();
+    for (row, column, values) in cells {
+        for value in values.iter() {
+            text.push_str(&format!("{},{},{value}\n", quote(row), quote(column)));
+        }
+    }
+    text
+}
+
+const BASE: [u128; 6] = [100, 102, 98, 101, 99, 100];
+const SLOWER: [u128; 6] = [150, 152, 148, 151, 149, 150];
+const FASTER: [u128; 6] = [50, 52, 48, 51, 49, 50];
+const NOISY: [u128; 6] = [60, 140, 80, 120, 100, 101];
+
+#[test]
+fn reads_cells_in_file_order() {
+    let file = TempFile::with_text(
+        "order.csv",
+        &raw_csv(&[
+            ("B, \"quoted\"", "20", &[1, 2]),
+            ("A", "10", &[3]),
+            ("B, \"quoted\"", "10", &[4]),
+        ]),
+    );
+    // cells do not have to be contiguous
+    let text = std::fs::read_to_string(&file.0).unwrap() + "\"A\",\"10\",\"5\"\n";
+    std::fs::write(&file.0, text).unwrap();
+
+    let measurements = Measurements::read_raw_csv(&file.0).unwrap();
+
+    let key = |row: &str, column: &str| (row.to_string(), column.to_string());
+    assert_eq!(
+        measurements.order,
+        vec![
+            key("B, \"quoted\"", "20"),
+            key("A", "10"),
+            key("B, \"quoted\"", "10")
+        ]
+    );
+    assert_eq!(measurements.get("A", "10"), Some([3, 5].as_slice()));
+    assert_eq!(
+        measurements.get("B, \"quoted\"", "20"),
+        Some([1, 2].as_slice())
+    );
+    assert_eq!(measurements.get("A", "20"), None);
+}
+
+#[test]
+fn rejects_files_that_are_not_raw() {
+    let cases = [
+        (
+            "header",
+            "\"Merge Sort implementations\",\"50\"\n",
+            "not a raw measurement file",
+        ),
+        (
+            "fields",
+            "row,column,value\nA,10\n",
+            "line 2 does not have 3 fields",
+        ),
+        (
+            "value",
+            "row,column,value\nA,10,1\nA,10,x\n",
+            "line 3: invalid digit",
+        ),
+    ];
+    for (name, text, message) in cases {
+        let file = TempFile::with_text(&format!("{name}.csv"), text);
+        let error = Measurements::read_raw_csv(&file.0).err().unwrap();
+        assert!(error.contains(message), "{name}: {error}");
+    }
+}
+
+#[test]
+fn verdicts() {
+    let base = TempFile::with_text(
+        "base.csv",
+        &raw_csv(&[
+            ("Slower", "10", &BASE),
+            ("Faster", "10", &BASE),
+            ("Noisy", "10", &BASE),
+            ("Single", "10", &[100]),
+            ("Missing", "10", &BASE),
+        ]),
+    );
+    let new = TempFile::with_text(
+        "new.csv",
+        &raw_csv(&[
+            ("Noisy", "10", &NOISY),
+            ("Faster", "10", &FASTER),
+            ("Slower", "10", &SLOWER),
+            ("Single", "10", &[100, 100]),
+        ]),
+    );
+
+    let comparisons = compare(
+        &Measurements::read_raw_csv(&base.0).unwrap(),
+        &Measurements::read_raw_csv(&new.0).unwrap(),
+    );
+
+    // in base file order, cells with one measurement or only in one file are skipped
+    let verdicts: Vec<(&str, Verdict)> = comparisons
+        .iter()
+        .map(|c| (c.row.as_str(), c.verdict))
+        .collect();
+    assert_eq!(
+        verdicts,
+        vec![
+            ("Slower", Verdict::Regressed),
+            ("Faster", Verdict::Improved),
+            ("Noisy", Verdict::NoChange),
+        ]
+    );
+    assert_eq!(comparisons[0].change, Some(50.));
+    assert_eq!(comparisons[1].change, Some(-50.));
+
+    assert!(compare_files(&base.0, &new.0, 5.).unwrap());
+    // the regression is 50%
+    assert!(!compare_files(&base.0, &new.0, 60.).unwrap());
+    assert!(!compare_files(&new.0, &new.0, 5.).unwrap());
+}
+
+#[test]
+fn zero_base_mean_has_no_change_but_can_regress() {
+    let base = TempFile::with_text("zero_base.csv", &raw_csv(&[("Zero", "10", &[0, 0, 0, 0])]));
+    let new = TempFile::with_text(
+        "zero_new.csv",
+        &raw_csv(&[("Zero", "10", &[10, 11, 9, 10])]),
+    );
+
+    let comparisons = compare(
+        &Measurements::read_raw_csv(&base.0).unwrap(),
+        &Measurements::read_raw_csv(&new.0).unwrap(),
+    );
+
+    assert_eq!(comparisons.len(), 1);
+    assert_eq!(comparisons[0].change, None);
+    assert_eq!(comparisons[0].verdict, Verdict::Regressed);
+    assert!(compare_files(&base.0, &new.0, 1000.).unwrap());
+    assert!(!compare_files(&base.0, &base.0, 5.).unwrap());
+}
+
+#[test]
+fn differences_without_noise_are_significant() {
+    let base = TempFile::with_text(
+        "constant_base.csv",
+        &raw_csv(&[("Same", "10", &[100; 5]), ("Slower", "10", &[100; 5])]),
+    );
+    let new = TempFile::with_text(
+        "constant_new.csv",
+        &raw_csv(&[("Same", "10", &[100; 5]), ("Slower", "10", &[200; 5])]),
+    );
+
+    let comparisons = compare(
+        &Measurements::read_raw_csv(&base.0).unwrap(),
+        &Measurements::read_raw_csv(&new.0).unwrap(),
+    );
+
+    assert_eq!(comparisons[0].t, 0.);
+    assert_eq!(comparisons[0].verdict, Verdict::NoChange);
+    assert_eq!(comparisons[1].t, f64::INFINITY);
+    assert_eq!(comparisons[1].change, Some(100.));
+    assert_eq!(comparisons[1].verdict, Verdict::Regressed);
+    assert!(compare_files(&base.0, &new.0, 5.).unwrap());
+    assert!(!compare_files(&new.0, &base.0, 5.).unwrap());
+}
+
+#[test]
+fn small_cells_need_a_larger_t() {
+    // t is 2.83 with 2 degrees of freedom, significant would need 4.30
+    let base = TempFile::with_text("small_base.csv", &raw_csv(&[("Small", "10", &[100, 110])]));
+    let new = TempFile::with_text("small_new.csv", &raw_csv(&[("Small", "10", &[120, 130])]));
+
+    let comparisons = compare(
+        &Measurements::read_raw_csv(&base.0).unwrap(),
+        &Measurements::read_raw_csv(&new.0).unwrap(),
+    );
+
+    assert!((comparisons[0].t - 2.83).abs() < 0.01);
+    assert!((comparisons[0].degrees_of_freedom - 2.).abs() < 1e-9);
+    assert_eq!(comparisons[0].verdict, Verdict::NoChange);
+
+    // the same difference measured more often is significant
+    let many_base: Vec<u128> = [100, 110].repeat(10);
+    let many_new: Vec<u128> = [120, 130].repeat(10);
+    let base = TempFile::with_text("many_base.csv", &raw_csv(&[("Small", "10", &many_base)]));
+    let new = TempFile::with_text("many_new.csv", &raw_csv(&[("Small", "10", &many_new)]));
+
+    let comparisons = compare(
+        &Measurements::read_raw_csv(&base.0).unwrap(),
+        &Measurements::read_raw_csv(&new.0).unwrap(),
+    );
+
+    assert_eq!(comparisons[0].verdict, Verdict::Regressed);
+}
+
+#[test]
+fn files_without_common_cells_are_an_error() {
+    let base = TempFile::with_text("common_base.csv", &raw_csv(&[("A", "10", &BASE)]));
+    let new = TempFile::with_text("common_new.csv", &raw_csv(&[("B", "10", &BASE)]));
+
+    assert!(compare_files(&base.0, &new.0, 5.).is_err());
+}
@@ -0,0 +1,207 @@
+//! Merges experiment files with command lines for every kind of option.
+
+#![cfg(feature = "bevy")]
+
+use clap::{ArgAction, Parser, ValueEnum};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use iteration2::config::parse_with_config;
+
+mod common;
+use common::TempFile;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Shape {
+    Round,
+    FlatTop,
+}
+
+#[derive(Parser, Debug, PartialEq)]
+struct Args {
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// SetTrue flag, off by default
+    #[arg(long, default_value_t = false)]
+    raw: bool,
+    /// SetTrue flag, on by default
+    #[arg(long, default_value_t = true)]
+    always: bool,
+    /// bool that can be turned off
+    #[arg(
+        short = 'D',
+        long,
+        default_value_t = true,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    debug: bool,
+    #[arg(short, long, default_value_t = 50)]
+    first: usize,
+    #[arg(long, default_value_t = 10.)]
+    duration: f32,
+    #[arg(long, value_enum, default_value_t = Shape::Round)]
+    shape: Shape,
+    #[arg(long, value_delimiter = ',')]
+    sizes: Vec<usize>,
+    #[arg(long = "library")]
+    libraries: Vec<String>,
+    #[arg(long)]
+    seed: Option<u64>,
+    #[arg(long, default_value_t = 0)]
+    warmup_frames: usize,
+}
+
+fn parse(file: &TempFile, command_line: &[&str]) -> Result<Args, String> {
+    let command_line: Vec<OsString> = ["program", "--config", file.0.to_str().unwrap()]
+        .iter()
+        .chain(command_line)
+        .map(OsString::from)
+        .collect();
+    parse_with_config(command_line, |args: &Args| args.config.as_ref())
+}
+
+fn defaults(file: &TempFile) -> Args {
+    Args {
+        config: Some(file.0.clone()),
+        raw: false,
+        always: true,
+        debug: true,
+        first: 50,
+        duration: 10.,
+        shape: Shape::Round,
+        sizes: Vec::new(),
+        libraries: Vec::new(),
+        seed: None,
+        warmup_frames: 0,
+    }
+}
+
+#[test]
+fn file_sets_every_kind_of_option() {
+    let file = TempFile::with_text(
+        "every_kind.toml",
+        r#"
+            raw = true
+            always = true
+            debug = false
+            first = 7
+            duration = 2.5
+            shape = "flat-top"
+            sizes = [10, 20, 40]
+            library = ["a.so", "b.so"]
+            seed = 42
+        "#,
+    );
+
+    let args = parse(&file, &[]).unwrap();
+
+    assert_eq!(
+        args,
+        Args {
+            raw: true,
+            debug: false,
+            first: 7,
+            duration: 2.5,
+            shape: Shape::FlatTop,
+            sizes: vec![10, 20, 40],
+            libraries: vec!["a.so".to_string(), "b.so".to_string()],
+            seed: Some(42),
+            ..defaults(&file)
+        }
+    );
+}
+
+#[test]
+fn command_line_wins_over_the_file() {
+    let file = TempFile::with_text(
+        "override.toml",
+        r#"
+            debug = false
+            first = 7
+            duration = 2.5
+            shape = "flat-top"
+            sizes = [10, 20, 40]
+            library = ["a.so", "b.so"]
+            seed = 42
+        "#,
+    );
+
+    let args = parse(
+        &file,
+        &[
+            "-D",
+            "-f",
+            "9",
+            "--duration=1",
+            "--shape",
+            "round",
+            "--sizes",
+            "5,6",
+            "--library",
+            "c.so",
+            "--seed",
+            "1",
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        args,
+        Args {
+            first: 9,
+            duration: 1.,
+            sizes: vec![5, 6],
+            libraries: vec!["c.so".to_string()],
+            seed: Some(1),
+            ..defaults(&file)
+        }
+    );
+}
+
+#[test]
+fn keys_can_use_underscores_and_false_flags_that_are_off() {
+    let file = TempFile::with_text("underscores.toml", "raw = false\nwarmup_frames = 3\n");
+
+    let args = parse(&file, &[]).unwrap();
+
+    assert_eq!(
+        args,
+        Args {
+            warmup_frames: 3,
+            ..defaults(&file)
+        }
+    );
+}
+
+#[test]
+fn invalid_files_are_errors() {
+    let cases = [
+        ("unknown", "bogus = 1", "unknown option bogus"),
+        (
+            "nested",
+            "config = \"other.toml\"",
+            "can not include another",
+        ),
+        ("flag_type", "raw = 1", "raw must be true or false"),
+        (
+            "flag_on",
+            "always = false",
+            "always is on by default and can not be turned off",
+        ),
+        (
+            "table",
+            "first = { a = 1 }",
+            "first must be a value or a list of values",
+        ),
+        ("syntax", "first = ", "syntax.toml"),
+    ];
+
+    for (name, text, message) in cases {
+        let file = TempFile::with_text(&format!("{name}.toml"), text);
+        let error = parse(&file, &[]).unwrap_err();
+        assert!(error.contains(message), "{name}: {error}");
+    }
+}
@@ -0,0 +1,143 @@
+//! Runs a headless experiment with the library systems and counts what gets recorded.
+
+#![cfg(feature = "bevy")]
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use iteration2::algorithms::SortOrder;
+use iteration2::ball::*;
+use iteration2::dynamic::DynamicSorts;
+use iteration2::experiment::*;
+use iteration2::key::SortKey;
+use iteration2::metric::{DistanceMetric, Metric};
+use iteration2::profiler::{Profiler, ProfilerPlugin};
+use iteration2::random::SeededRng;
+use iteration2::sorting::*;
+use iteration2::spawn::{SpawnDistribution, SpawnSettings};
+use iteration2::target::TargetShape;
+use iteration2::timing::TimingScope;
+
+const SAMPLE_SIZES: [usize; 2] = [10, 25];
+const VARIATIONS: [usize; 2] = [0, 2];
+const MIN_CALCS: usize = 5;
+
+/// frames sorted per (variation, sample) block, measured or not
+#[derive(Resource, Default)]
+struct SortedFrames(Vec<((usize, usize), usize)>);
+
+fn count_sorted_frames(exp_params: Res<ExperimentParameters>, mut frames: ResMut<SortedFrames>) {
+    let block = (exp_params.variation_index, exp_params.sample_index);
+    match frames.0.last_mut() {
+        Some((last, count)) if *last == block => *count += 1,
+        _ => frames.0.push((block, 1)),
+    }
+}
+
+fn run(warmup_frames: usize, block_order: BlockOrder) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_message::<MarkBallMessage>()
+        .insert_resource(SeededRng::new(1))
+        .insert_resource(SpawnSettings::new(SpawnDistribution::Uniform, 4, 40.))
+        .insert_resource(Arena(Vec2::new(1280., 720.)))
+        .insert_resource(SortScratch::new(false))
+        .insert_resource(TimingScope::SortOnly)
+        .insert_resource(DynamicSorts::default())
+        .insert_resource(Variations {
+            names: VARIATION_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            selected: VARIATIONS.to_vec(),
+        })
+        .init_resource::<SortedFrames>()
+        .add_plugins(ProfilerPlugin)
+        .add_plugins(ExperimentPlugin {
+            sample_sizes: SAMPLE_SIZES.to_vec(),
+            variations: VARIATIONS.len(),
+            // long enough that the number of calculations ends every block
+            step_duration: Duration::from_secs(3600),
+            min_calcs_per_step: MIN_CALCS,
+            warmup_frames,
+            warmup_duration: Duration::ZERO,
+            pick_number: 3,
+            key: SortKey::Distance,
+            metric: Metric::new(DistanceMetric::SquaredEuclidean, Vec2::ONE).unwrap(),
+            target: TargetShape::Point,
+            order: SortOrder::Ascending,
+            farthest: false,
+            block_order,
+            seed: 1,
+            debug: false,
+        })
+        .add_systems(Startup, (setup_tables, add_ball))
+        .add_systems(
+            Update,
+            (
+                clear_balls
+                    .run_if(on_message::<ExperimentProgress>)
+                    .before(add_ball),
+                add_ball.run_if(on_message::<ExperimentProgress>),
+                sort_balls.after(add_ball),
+                count_sorted_frames.after(sort_balls),
+            ),
+        );
+
+    for _ in 0..1000 {
+        if app.should_exit().is_some() {
+            return app;
+        }
+        app.update();
+    }
+    panic!("the experiment did not finish");
+}
+
+fn recorded_counts(app: &App) -> Vec<usize> {
+    let profiler = app.world().resource::<Profiler>();
+    let table = profiler.get_table_ref(SORTING_TABLE.0).unwrap();
+    (0..VARIATIONS.len())
+        .flat_map(|row| (0..SAMPLE_SIZES.len()).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            table
+                .cell(row, column)
+                .map_or(0, |cell| cell.samples().len())
+        })
+        .collect()
+}
+
+#[test]
+fn warmup_frames_are_sorted_but_not_recorded() {
+    for warmup_frames in [0, 1, 4] {
+        let app = run(warmup_frames, BlockOrder::Sequential);
+
+        assert_eq!(
+            recorded_counts(&app),
+            vec![MIN_CALCS; 4],
+            "warmup {warmup_frames}"
+        );
+
+        let frames = &app.world().resource::<SortedFrames>().0;
+        assert_eq!(frames.len(), 4, "warmup {warmup_frames}");
+        for (block, count) in frames {
+            assert_eq!(
+                *count,
+                warmup_frames + MIN_CALCS,
+                "warmup {warmup_frames} block {block:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn every_block_order_fills_every_cell() {
+    for block_order in [
+        BlockOrder::Sequential,
+        BlockOrder::Interleaved,
+        BlockOrder::Randomized,
+    ] {
+        let app = run(2, block_order);
+
+        assert_eq!(recorded_counts(&app), vec![MIN_CALCS; 4], "{block_order:?}");
+    }
+}
@@ -0,0 +1,240 @@
+//! Exports a small table in every text format and checks the files word for word.
+
+#![cfg(feature = "std")]
+
+use iteration2::compare::Measurements;
+use iteration2::export::*;
+use iteration2::profiler::{Profiler, Statistic};
+use std::path::{Path, PathBuf};
+
+mod common;
+use common::TempDir;
+
+/// a row name that needs quoting in csv and json and escaping in markdown
+const QUICK: &str = "Quick, \"q\" | s";
+
+/// Merge has three measurements for 10 and one for 100, Quick has nothing for 100
+fn profiler() -> Profiler {
+    let mut profiler = Profiler::new();
+    let table = profiler.create_table(
+        "Sorts",
+        vec!["Merge".to_string(), QUICK.to_string()],
+        vec!["10".to_string(), "100".to_string()],
+    );
+    for value in [300, 100, 200] {
+        profiler.record_cell_data_by_table_row_col_index(table, 0, 0, value);
+    }
+    profiler.record_cell_data_by_table_row_col_index(table, 0, 1, 1000);
+    profiler.record_cell_data_by_table_row_col_index(table, 1, 0, 50);
+    profiler.add_metadata("seed", "1");
+    profiler.add_metadata("note", "a, b");
+    profiler
+}
+
+fn export(profiler: &Profiler, exporter: &dyn Exporter) -> Vec<PathBuf> {
+    exporter
+        .export(&Export {
+            table_name: &profiler.table_names[0],
+            table: &profiler.tables[0],
+            metadata: &profiler.metadata,
+            file_name: "sorts",
+        })
+        .unwrap()
+}
+
+/// the names of the files in `directory`, sorted
+fn names(directory: &TempDir) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(&directory.0)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn csv_writes_a_file_per_statistic_metadata_and_raw() {
+    let directory = TempDir::new("export_csv");
+    let profiler = profiler();
+    let exporter = CsvExporter {
+        statistics: vec![Statistic::Mean, Statistic::Count],
+        raw: true,
+        directory: directory.0.clone(),
+    };
+
+    let files = export(&profiler, &exporter);
+
+    assert_eq!(
+        files,
+        ["", "_count", "_metadata", "_raw"]
+            .map(|suffix| directory.0.join(format!("sorts{suffix}.csv")))
+    );
+    assert_eq!(
+        read(&files[0]),
+        "Sorts,10,100\nMerge,200,1000\n\"Quick, \"\"q\"\" | s\",50,0\n"
+    );
+    assert_eq!(
+        read(&files[1]),
+        "Sorts,10,100\nMerge,3,1\n\"Quick, \"\"q\"\" | s\",1,0\n"
+    );
+    assert_eq!(read(&files[2]), "seed,1\nnote,\"a, b\"\n");
+    // measurements keep the order they were recorded in
+    assert_eq!(
+        read(&files[3]),
+        "row,column,value\n\
+         Merge,10,300\n\
+         Merge,10,100\n\
+         Merge,10,200\n\
+         Merge,100,1000\n\
+         \"Quick, \"\"q\"\" | s\",10,50\n"
+    );
+
+    // compare reads what the exporter writes
+    let measurements = Measurements::read_raw_csv(&files[3]).unwrap();
+    assert_eq!(
+        measurements.get("Merge", "10"),
+        Some([300, 100, 200].as_slice())
+    );
+    assert_eq!(measurements.get(QUICK, "10"), Some([50].as_slice()));
+    assert_eq!(measurements.get(QUICK, "100"), None);
+}
+
+#[test]
+fn csv_without_metadata_or_raw_writes_only_statistics() {
+    let directory = TempDir::new("export_csv_plain");
+    let mut profiler = profiler();
+    profiler.metadata.clear();
+    let exporter = CsvExporter {
+        statistics: vec![Statistic::Median],
+        raw: false,
+        directory: directory.0.clone(),
+    };
+
+    let files = export(&profiler, &exporter);
+
+    assert_eq!(names(&directory), ["sorts_median.csv"]);
+    assert_eq!(
+        read(&files[0]),
+        "Sorts,10,100\nMerge,200,1000\n\"Quick, \"\"q\"\" | s\",50,0\n"
+    );
+}
+
+#[test]
+fn json_has_every_statistic_and_null_for_empty_cells() {
+    let directory = TempDir::new("export_json");
+    let profiler = profiler();
+    let exporter = JsonExporter {
+        statistics: vec![Statistic::Mean, Statistic::Count],
+        raw: true,
+        directory: directory.0.clone(),
+    };
+
+    let files = export(&profiler, &exporter);
+
+    assert_eq!(files, [directory.0.join("sorts.json")]);
+    assert_eq!(
+        read(&files[0]),
+        r#"{
+  "table": "Sorts",
+  "metadata": {"seed": "1", "note": "a, b"},
+  "columns": ["10", "100"],
+  "rows": [
+    {
+      "name": "Merge",
+      "cells": [
+        {"mean": 200, "count": 3, "raw": [300, 100, 200]},
+        {"mean": 1000, "count": 1, "raw": [1000]}
+      ]
+    },
+    {
+      "name": "Quick, \"q\" | s",
+      "cells": [
+        {"mean": 50, "count": 1, "raw": [50]},
+        {"mean": null, "count": null, "raw": []}
+      ]
+    }
+  ]
+}
+"#
+    );
+}
+
+#[test]
+fn markdown_has_a_table_per_statistic() {
+    let directory = TempDir::new("export_markdown");
+    let profiler = profiler();
+    let exporter = MarkdownExporter {
+        statistics: vec![Statistic::Mean, Statistic::Max],
+        directory: directory.0.clone(),
+    };
+
+    let files = export(&profiler, &exporter);
+
+    assert_eq!(files, [directory.0.join("sorts.md")]);
+    assert_eq!(
+        read(&files[0]),
+        r#"## Sorts
+
+- **seed**: 1
+- **note**: a, b
+
+### mean
+
+|  | 10 | 100 |
+|:---|---:|---:|
+| Merge | 200.0 | 1000.0 |
+| Quick, "q" \| s | 50.0 | 0.0 |
+
+### max
+
+|  | 10 | 100 |
+|:---|---:|---:|
+| Merge | 300.0 | 1000.0 |
+| Quick, "q" \| s | 50.0 | 0.0 |
+"#
+    );
+}
+
+#[test]
+fn formats_pick_their_exporter_and_write_to_the_output_directory() {
+    let profiler = profiler();
+
+    for (format, expected) in [
+        (
+            Format::Csv,
+            ["sorts.csv", "sorts_metadata.csv", "sorts_raw.csv"].as_slice(),
+        ),
+        (Format::Json, &["sorts.json"]),
+        (Format::Markdown, &["sorts.md"]),
+        (Format::Svg, &["sorts.svg"]),
+    ] {
+        // the directory does not exist yet
+        let directory = TempDir::new(&format!("export_{format:?}"));
+        let options = ExportOptions {
+            statistics: vec![Statistic::Mean],
+            raw: true,
+            chart: iteration2::chart::ChartOptions {
+                statistic: Statistic::Mean,
+                error_bars: None,
+                log_x: false,
+                log_y: false,
+            },
+            directory: directory.0.join("nested"),
+        };
+
+        let files = export(&profiler, format.exporter(&options).as_ref());
+
+        let expected: Vec<PathBuf> = expected
+            .iter()
+            .map(|name| options.directory.join(name))
+            .collect();
+        assert_eq!(files, expected, "{format:?}");
+        for file in &files {
+            assert!(file.exists(), "{}", file.display());
+        }
+    }
+}
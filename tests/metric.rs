@@ -0,0 +1,76 @@
+//! Distances of every metric between points that differ in x, y and z,
+//! and the weights the weighted metric rejects.
+
+#![cfg(feature = "bevy")]
+
+use bevy::math::{Vec2, Vec3};
+use iteration2::metric::*;
+
+fn distance(kind: DistanceMetric, weights: Vec2) -> f32 {
+    // dx -3, dy 4, z is ignored
+    Metric::new(kind, weights)
+        .unwrap()
+        .distance(Vec3::new(1., 2., 5.), Vec3::new(4., -2., -1.))
+}
+
+#[test]
+fn every_metric() {
+    assert_eq!(distance(DistanceMetric::SquaredEuclidean, Vec2::ONE), 25.);
+    assert_eq!(distance(DistanceMetric::Manhattan, Vec2::ONE), 7.);
+    assert_eq!(distance(DistanceMetric::Chebyshev, Vec2::ONE), 4.);
+    assert_eq!(distance(DistanceMetric::WeightedEuclidean, Vec2::ONE), 25.);
+    assert_eq!(
+        distance(DistanceMetric::WeightedEuclidean, Vec2::new(2., 0.5)),
+        2. * 9. + 0.5 * 16.
+    );
+    // a zero weight ignores that axis
+    assert_eq!(
+        distance(DistanceMetric::WeightedEuclidean, Vec2::new(0., 1.)),
+        16.
+    );
+}
+
+#[test]
+fn same_point_is_at_zero() {
+    let a = Vec3::new(-3., 7., 0.);
+    for kind in [
+        DistanceMetric::SquaredEuclidean,
+        DistanceMetric::Manhattan,
+        DistanceMetric::Chebyshev,
+        DistanceMetric::WeightedEuclidean,
+    ] {
+        let metric = Metric::new(kind, Vec2::new(2., 3.)).unwrap();
+        assert_eq!(metric.distance(a, a), 0., "{kind:?}");
+    }
+}
+
+#[test]
+fn rejects_weights_that_are_not_distances() {
+    for weights in [
+        Vec2::new(-1., 1.),
+        Vec2::new(1., -0.5),
+        Vec2::ZERO,
+        Vec2::new(f32::NAN, 1.),
+        Vec2::new(f32::INFINITY, 1.),
+    ] {
+        assert!(
+            Metric::new(DistanceMetric::WeightedEuclidean, weights).is_err(),
+            "{weights}"
+        );
+    }
+}
+
+#[test]
+fn other_metrics_ignore_the_weights() {
+    for kind in [
+        DistanceMetric::SquaredEuclidean,
+        DistanceMetric::Manhattan,
+        DistanceMetric::Chebyshev,
+    ] {
+        assert_eq!(
+            distance(kind, Vec2::new(-1., -1.)),
+            distance(kind, Vec2::ONE),
+            "{kind:?}"
+        );
+    }
+}
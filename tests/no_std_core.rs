@@ -0,0 +1,177 @@
+//! Checks the sorting core on its own, run with
+//! `cargo nostd` (`cargo test --no-default-features --test no_std_core`) to build the library as `no_std`,
+//! CI runs it on every push.
+
+use iteration2::algorithms::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// counts the allocations of the current thread, the tests run in parallel
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// small xorshift so the test needs no dependencies
+fn keys(length: usize, seed: u32) -> Vec<f32> {
+    let mut state = seed.max(1);
+    (0..length)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            // few distinct values so there are plenty of ties
+            (state % 64) as f32
+        })
+        .collect()
+}
+
+fn ball_data(keys: &[f32]) -> Vec<BallData<usize>> {
+    keys.iter()
+        .enumerate()
+        .map(|(entity, distance)| BallData { entity, distance: *distance })
+        .collect()
+}
+
+/// stable reference sort, the entity is the original position
+fn expected(keys: &[f32], order: SortOrder) -> Vec<(usize, f32)> {
+    let mut expected: Vec<(usize, f32)> = keys.iter().copied().enumerate().collect();
+    match order {
+        SortOrder::Ascending => expected.sort_by(|a, b| a.1.total_cmp(&b.1)),
+        SortOrder::Descending => expected.sort_by(|a, b| b.1.total_cmp(&a.1)),
+    }
+    expected
+}
+
+fn as_pairs(balls: &[BallData<usize>]) -> Vec<(usize, f32)> {
+    balls.iter().map(|ball| (ball.entity, ball.distance)).collect()
+}
+
+const LENGTHS: [usize; 9] = [0, 1, 2, 3, 4, 7, 16, 33, 1000];
+const ORDERS: [SortOrder; 2] = [SortOrder::Ascending, SortOrder::Descending];
+
+#[test]
+fn merge_sort_is_stable_and_sorted() {
+    for order in ORDERS {
+        for length in LENGTHS {
+            let keys = keys(length, length as u32);
+            let mut balls = ball_data(&keys);
+            let mut temp = ball_data(&keys);
+
+            merge_sort(&mut balls, &mut temp, order);
+
+            assert_eq!(as_pairs(&balls), expected(&keys, order), "length {length} {order:?}");
+        }
+    }
+}
+
+#[test]
+fn merge_bottom_is_stable_and_sorted() {
+    for order in ORDERS {
+        for length in LENGTHS {
+            let keys = keys(length, length as u32);
+            let mut balls = ball_data(&keys);
+            let mut temp = ball_data(&keys);
+
+            merge_bottom(&mut balls, &mut temp, order);
+
+            assert_eq!(as_pairs(&balls), expected(&keys, order), "length {length} {order:?}");
+        }
+    }
+}
+
+#[test]
+fn merge_top_is_stable_and_sorted() {
+    let mut pool = QueuePool::default();
+    for order in ORDERS {
+        for length in LENGTHS {
+            let keys = keys(length, length as u32);
+
+            let sorted = merge_top(ball_data(&keys).into(), order, &mut pool);
+
+            let sorted: Vec<BallData<usize>> = sorted.into();
+            assert_eq!(as_pairs(&sorted), expected(&keys, order), "length {length} {order:?}");
+        }
+    }
+}
+
+#[test]
+fn merge_top_does_not_allocate_after_warm() {
+    // lengths just above powers of two need the most room
+    let lengths = LENGTHS.into_iter().chain([1025, 4097, 100_000]);
+    let mut shared = QueuePool::default();
+    for length in lengths {
+        let keys = keys(length, length as u32);
+        let mut fresh = QueuePool::default();
+        // about 3 times the input, never a full copy per queue.
+        // A pool warmed for smaller lengths before keeps their queues as well
+        for (pool, bound) in [(&mut fresh, 4 * length + 64), (&mut shared, 8 * length + 64)] {
+            pool.warm(length, BallData { entity: 0, distance: 0. });
+            assert!(
+                pool.capacity() <= bound,
+                "length {length} pool {}",
+                pool.capacity()
+            );
+
+            for _ in 0..3 {
+                let mut unsorted = pool.take(length);
+                unsorted.extend(ball_data(&keys));
+
+                let before = allocations();
+                let sorted = merge_top(unsorted, SortOrder::Ascending, pool);
+                pool.give(sorted);
+
+                assert_eq!(allocations(), before, "length {length}");
+            }
+        }
+    }
+}
+
+#[test]
+fn soa_sorts_permute_indices_with_their_keys() {
+    type SoaSort = fn(&mut [f32], &mut [u32], &mut [f32], &mut [u32], SortOrder);
+    let sorts: [(&str, SoaSort); 2] = [
+        ("merge_sort_soa", merge_sort_soa),
+        ("merge_bottom_soa", merge_bottom_soa),
+    ];
+
+    for (name, sort) in sorts {
+        for order in ORDERS {
+            for length in LENGTHS {
+                let keys = keys(length, length as u32);
+                let mut sorted_keys = keys.clone();
+                let mut indices: Vec<u32> = (0..length as u32).collect();
+                let mut temp_keys = vec![0.; length];
+                let mut temp_indices = vec![0; length];
+
+                sort(&mut sorted_keys, &mut indices, &mut temp_keys, &mut temp_indices, order);
+
+                let actual: Vec<(usize, f32)> = indices
+                    .iter()
+                    .zip(sorted_keys)
+                    .map(|(index, key)| (*index as usize, key))
+                    .collect();
+                assert_eq!(actual, expected(&keys, order), "{name} length {length} {order:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Sample size progressions, their overflow cutoff and the parameters they reject.
+
+#![cfg(feature = "bevy")]
+
+use iteration2::experiment::*;
+
+fn sizes(
+    progression: Progression,
+    first: usize,
+    step: usize,
+    ratio: f64,
+    last: usize,
+    count: usize,
+) -> Vec<usize> {
+    generate_sample_sizes(progression, first, step, ratio, last, count).unwrap()
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(
+        sizes(Progression::Arithmetic, 50, 25, 2., 0, 5),
+        [50, 75, 100, 125, 150]
+    );
+    assert_eq!(sizes(Progression::Arithmetic, 0, 1, 2., 0, 3), [0, 1, 2]);
+    assert_eq!(sizes(Progression::Arithmetic, 7, 3, 2., 0, 0), []);
+    // far more than the 100 steps the old fixed array had
+    let many = sizes(Progression::Arithmetic, 1, 1, 2., 0, 1000);
+    assert_eq!(many.len(), 1000);
+    assert_eq!(many[999], 1000);
+}
+
+#[test]
+fn geometric() {
+    assert_eq!(
+        sizes(Progression::Geometric, 10, 0, 2., 0, 5),
+        [10, 20, 40, 80, 160]
+    );
+    assert_eq!(
+        sizes(Progression::Geometric, 100, 0, 1.5, 0, 4),
+        [100, 150, 225, 338]
+    );
+}
+
+#[test]
+fn log() {
+    assert_eq!(
+        sizes(Progression::Log, 10, 0, 2., 10_000, 4),
+        [10, 100, 1000, 10_000]
+    );
+    assert_eq!(sizes(Progression::Log, 1, 0, 2., 100, 3), [1, 10, 100]);
+    assert_eq!(sizes(Progression::Log, 10, 0, 2., 100, 1), [10]);
+}
+
+#[test]
+fn pow2() {
+    assert_eq!(sizes(Progression::Pow2, 1, 0, 2., 0, 5), [1, 2, 4, 8, 16]);
+    // starts at the first power of two that is not below first
+    assert_eq!(sizes(Progression::Pow2, 100, 0, 2., 0, 3), [128, 256, 512]);
+    assert_eq!(sizes(Progression::Pow2, 0, 0, 2., 0, 2), [1, 2]);
+}
+
+#[test]
+fn rounding_collisions_keep_sizes_increasing() {
+    // 2, 2.2, 2.42, 2.66, 2.93, 3.22 round to 2, 2, 2, 3, 3, 3
+    assert_eq!(
+        sizes(Progression::Geometric, 2, 0, 1.1, 0, 6),
+        [2, 3, 4, 5, 6, 7]
+    );
+    // 1, 1.29, 1.67, 2.15, 2.78, ... round to 1, 1, 2, 2, 3, ...
+    assert_eq!(
+        sizes(Progression::Log, 1, 0, 2., 10, 10),
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+    );
+}
+
+#[test]
+fn log_sizes_stay_between_first_and_last() {
+    for first in 1..20 {
+        for last in first + 1..60 {
+            for count in 2..=last - first + 1 {
+                let log = sizes(Progression::Log, first, 0, 2., last, count);
+                assert_eq!(log.len(), count, "{first} to {last} in {count}");
+                assert_eq!(log[0], first, "{first} to {last} in {count}");
+                assert_eq!(log[count - 1], last, "{first} to {last} in {count}");
+                assert!(log.windows(2).all(|pair| pair[0] < pair[1]));
+            }
+        }
+    }
+}
+
+#[test]
+fn overflow_stops_the_progression() {
+    let pow2 = sizes(Progression::Pow2, 1, 0, 2., 0, 200);
+    assert_eq!(pow2.len(), usize::BITS as usize);
+    assert_eq!(pow2.last(), Some(&(1 << (usize::BITS - 1))));
+
+    let geometric = sizes(Progression::Geometric, 1, 0, 10., 0, 200);
+    assert!(geometric.len() < 200);
+    assert!(geometric.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let arithmetic = sizes(Progression::Arithmetic, usize::MAX - 10, 5, 2., 0, 10);
+    assert_eq!(arithmetic, [usize::MAX - 10, usize::MAX - 5, usize::MAX]);
+
+    // the count is not allocated up front
+    let unbounded = sizes(Progression::Pow2, 1, 0, 2., 0, usize::MAX);
+    assert_eq!(unbounded.len(), usize::BITS as usize);
+}
+
+#[test]
+fn parameters_that_can_not_grow_are_rejected() {
+    let cases = [
+        ("step 0", Progression::Arithmetic, 10, 0, 2., 0),
+        ("ratio 1", Progression::Geometric, 10, 0, 1., 0),
+        ("ratio below 1", Progression::Geometric, 10, 0, 0.5, 0),
+        ("ratio nan", Progression::Geometric, 10, 0, f64::NAN, 0),
+        (
+            "ratio infinite",
+            Progression::Geometric,
+            10,
+            0,
+            f64::INFINITY,
+            0,
+        ),
+        ("geometric first 0", Progression::Geometric, 0, 0, 2., 0),
+        ("log first above last", Progression::Log, 100, 0, 2., 10),
+        ("log first equals last", Progression::Log, 100, 0, 2., 100),
+        ("log first 0", Progression::Log, 0, 0, 2., 100),
+        // only 10, 11 and 12 lie between first and last
+        ("log more steps than sizes", Progression::Log, 10, 0, 2., 12),
+    ];
+
+    for (name, progression, first, step, ratio, last) in cases {
+        assert!(
+            generate_sample_sizes(progression, first, step, ratio, last, 5).is_err(),
+            "{name}"
+        );
+    }
+    assert!(generate_sample_sizes(Progression::Log, 10, 0, 2., 12, 3).is_ok());
+    // parameters of the other progressions are ignored
+    assert!(generate_sample_sizes(Progression::Pow2, 10, 0, 0., 0, 5).is_ok());
+}
+
+#[test]
+fn explicit_sizes_must_be_unique() {
+    assert!(check_sample_sizes(&[10, 5, 20]).is_ok());
+    assert!(check_sample_sizes(&[]).is_ok());
+    assert_eq!(
+        check_sample_sizes(&[10, 20, 10]),
+        Err("the sample size 10 is given more than once".to_string())
+    );
+}
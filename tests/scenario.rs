@@ -0,0 +1,64 @@
+//! Writes a scenario with a special ball and a few others, reads it back field by field
+//! and checks the line the reader blames in malformed scenario files.
+
+#![cfg(feature = "bevy")]
+
+use bevy::math::Vec2;
+
+use iteration2::scenario::*;
+
+mod common;
+use common::TempFile;
+
+#[test]
+fn scenario_round_trip() {
+    let ball = |i: f32| BallState {
+        radius: 15.,
+        position: Vec2::new(i, i * -2.5),
+        velocity: Vec2::new(i * 10., 0.125),
+    };
+    let scenario = Scenario {
+        special: ball(-1.),
+        balls: (0..4).map(|i| ball(i as f32)).collect(),
+    };
+
+    let file = TempFile::new("round_trip.scenario");
+    scenario.write(&file.0).unwrap();
+    let read = Scenario::read(&file.0).unwrap();
+
+    let state = |ball: &BallState| (ball.radius, ball.position, ball.velocity);
+    assert_eq!(state(&read.special), state(&scenario.special));
+    assert_eq!(
+        read.balls.iter().map(state).collect::<Vec<_>>(),
+        scenario.balls.iter().map(state).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn scenario_rejects_broken_files() {
+    let cases = [
+        ("no_special", "ball,1,2,3,4,5\n", "no special ball"),
+        (
+            "two_special",
+            "special,1,2,3,4,5\nspecial,1,2,3,4,5\n",
+            "2: more than one special ball",
+        ),
+        (
+            "values",
+            "# comment\n\nspecial,1,2,3\n",
+            "3: expected 6 values but found 4",
+        ),
+        ("number", "special,1,2,x,4,5\n", "1: invalid float literal"),
+        (
+            "kind",
+            "special,1,2,3,4,5\nsquare,1,2,3,4,5\n",
+            "2: unknown kind square",
+        ),
+    ];
+
+    for (name, text, message) in cases {
+        let file = TempFile::with_text(&format!("{name}.scenario"), text);
+        let error = Scenario::read(&file.0).unwrap_err();
+        assert!(error.contains(message), "{name}: {error}");
+    }
+}
@@ -0,0 +1,100 @@
+//! Places balls with every distribution and checks how many there are, that they
+//! stay in the arena and how mixed splits them between the other distributions.
+
+#![cfg(feature = "bevy")]
+
+use bevy::math::{Rect, Vec2};
+use iteration2::spawn::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+const DISTRIBUTIONS: [SpawnDistribution; 6] = [
+    SpawnDistribution::Uniform,
+    SpawnDistribution::Clusters,
+    SpawnDistribution::Ring,
+    SpawnDistribution::Line,
+    SpawnDistribution::Grid,
+    SpawnDistribution::Mixed,
+];
+
+/// the arena is off center so positions around the origin would be outside
+const AREA: Rect = Rect {
+    min: Vec2::new(100., -50.),
+    max: Vec2::new(400., 150.),
+};
+
+/// near a corner so rings and clusters reach past the edges
+const SPECIAL: Vec2 = Vec2::new(110., -40.);
+
+fn positions(distribution: SpawnDistribution, count: usize) -> Vec<Vec2> {
+    SpawnSettings::new(distribution, 3, 80.).positions(
+        count,
+        AREA,
+        SPECIAL,
+        &mut StdRng::seed_from_u64(7),
+    )
+}
+
+#[test]
+fn every_distribution_places_count_balls_inside_the_area() {
+    for distribution in DISTRIBUTIONS {
+        for count in [0, 1, 2, 7, 100, 1001] {
+            let positions = positions(distribution, count);
+
+            assert_eq!(positions.len(), count, "{distribution:?} {count}");
+            for position in positions {
+                assert!(
+                    AREA.contains(position),
+                    "{distribution:?} {count}: {position} outside"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn mixed_gives_the_remainder_to_the_first_distributions() {
+    for (count, shares) in [(7, [2, 2, 1, 1, 1]), (3, [1, 1, 1, 0, 0]), (10, [2; 5])] {
+        let settings = SpawnSettings::new(SpawnDistribution::Mixed, 3, 80.);
+        let mixed = settings.positions(count, AREA, SPECIAL, &mut StdRng::seed_from_u64(7));
+
+        // the same random stream split over the parts one after the other
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut expected = Vec::new();
+        for (distribution, share) in DISTRIBUTIONS.into_iter().zip(shares) {
+            expected.extend(
+                SpawnSettings::new(distribution, 3, 80.).positions(share, AREA, SPECIAL, &mut rng),
+            );
+        }
+        assert_eq!(mixed, expected, "{count}");
+    }
+}
+
+#[test]
+fn grid_fills_rows_of_cell_centers() {
+    assert!(positions(SpawnDistribution::Grid, 0).is_empty());
+    assert_eq!(positions(SpawnDistribution::Grid, 1), [AREA.center()]);
+
+    // 3 columns 100 wide and 2 rows 100 high, the last cell stays empty
+    assert_eq!(
+        positions(SpawnDistribution::Grid, 5),
+        [
+            Vec2::new(150., 0.),
+            Vec2::new(250., 0.),
+            Vec2::new(350., 0.),
+            Vec2::new(150., 100.),
+            Vec2::new(250., 100.),
+        ]
+    );
+}
+
+#[test]
+fn same_seed_same_positions() {
+    for distribution in DISTRIBUTIONS {
+        assert_eq!(
+            positions(distribution, 50),
+            positions(distribution, 50),
+            "{distribution:?}"
+        );
+    }
+}
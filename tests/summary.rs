@@ -0,0 +1,62 @@
+//! Checks every statistic of a cell against values worked out by hand,
+//! including the cells with one and two measurements.
+
+#![cfg(feature = "std")]
+
+use iteration2::profiler::{Statistic, Summary};
+
+const STATISTICS: [Statistic; 10] = [
+    Statistic::Mean,
+    Statistic::Median,
+    Statistic::Min,
+    Statistic::Max,
+    Statistic::Stddev,
+    Statistic::P5,
+    Statistic::P95,
+    Statistic::P99,
+    Statistic::Ci95,
+    Statistic::Count,
+];
+
+fn assert_statistics(samples: &[u128], expected: [f64; 10]) {
+    let summary = Summary::new(samples).unwrap();
+    for (statistic, expected) in STATISTICS.into_iter().zip(expected) {
+        let actual = summary.get(statistic);
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} of {samples:?}: {actual} instead of {expected}",
+            statistic.name()
+        );
+    }
+}
+
+#[test]
+fn eight_measurements() {
+    // sorted 2 4 4 4 5 5 7 9, the median is halfway between the 4th and 5th value.
+    // p5, p95 and p99 interpolate at ranks 0.35, 6.65 and 6.93.
+    // The squared deviations add up to 32, so stddev is sqrt(32 / 7)
+    // and ci95 is 1.96 * stddev / sqrt(8)
+    assert_statistics(
+        &[5, 9, 2, 4, 7, 4, 5, 4],
+        [5., 4.5, 2., 9., 2.13809, 2.7, 8.3, 8.86, 1.48162, 8.],
+    );
+}
+
+#[test]
+fn one_measurement_has_no_spread() {
+    assert_statistics(&[7], [7., 7., 7., 7., 0., 7., 7., 7., 0., 1.]);
+}
+
+#[test]
+fn two_measurements() {
+    // stddev sqrt(50), ci95 1.96 * sqrt(50) / sqrt(2)
+    assert_statistics(
+        &[20, 10],
+        [15., 15., 10., 20., 7.07107, 10.5, 19.5, 19.9, 9.8, 2.],
+    );
+}
+
+#[test]
+fn no_measurements_have_no_summary() {
+    assert!(Summary::new(&[]).is_none());
+}
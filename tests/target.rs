@@ -0,0 +1,94 @@
+//! Closest points of every target shape, including the segment clamped at both
+//! ends, the zero length segment and points inside a rect.
+
+#![cfg(feature = "bevy")]
+
+use bevy::math::{Vec2, Vec3};
+use iteration2::metric::{DistanceMetric, Metric};
+use iteration2::target::*;
+
+const CENTER: Vec2 = Vec2::new(10., -5.);
+
+fn assert_close(actual: Vec2, expected: Vec2) {
+    assert!(
+        actual.abs_diff_eq(expected, 1e-4),
+        "{actual} instead of {expected}"
+    );
+}
+
+fn closest(shape: TargetShape, offset: Vec2) -> Vec2 {
+    shape.closest_point(CENTER, CENTER + offset) - CENTER
+}
+
+#[test]
+fn point_is_its_own_closest_point() {
+    let shape = TargetShape::new(TargetKind::Point, Vec2::new(4., 2.), 30.);
+    assert_close(closest(shape, Vec2::new(3., 7.)), Vec2::ZERO);
+}
+
+#[test]
+fn line_projects_onto_its_direction() {
+    let shape = TargetShape::new(TargetKind::Line, Vec2::ZERO, 45.);
+
+    // far past where a segment would end
+    assert_close(closest(shape, Vec2::new(100., 0.)), Vec2::splat(50.));
+    assert_close(closest(shape, Vec2::new(-3., -1.)), Vec2::splat(-2.));
+    // points on the line are their own closest point
+    assert_close(closest(shape, Vec2::splat(7.)), Vec2::splat(7.));
+}
+
+#[test]
+fn segment_clamps_at_both_ends() {
+    // 4 long along x, the ends are 2 away from the center
+    let shape = TargetShape::new(TargetKind::Segment, Vec2::new(4., 0.), 0.);
+
+    assert_close(closest(shape, Vec2::new(1., 3.)), Vec2::new(1., 0.));
+    assert_close(closest(shape, Vec2::new(5., 3.)), Vec2::new(2., 0.));
+    assert_close(closest(shape, Vec2::new(-5., -3.)), Vec2::new(-2., 0.));
+    assert_close(closest(shape, Vec2::new(0., 9.)), Vec2::ZERO);
+}
+
+#[test]
+fn zero_length_segment_is_a_point() {
+    let shape = TargetShape::new(TargetKind::Segment, Vec2::ZERO, 60.);
+
+    assert_close(closest(shape, Vec2::new(3., -4.)), Vec2::ZERO);
+    assert_eq!(shape.describe(), "point");
+}
+
+#[test]
+fn rect_clamps_outside_points_and_keeps_inside_ones() {
+    // 6 wide and 4 high
+    let shape = TargetShape::new(TargetKind::Rect, Vec2::new(6., 4.), 0.);
+
+    // inside and on the edge
+    assert_close(closest(shape, Vec2::new(1., -1.5)), Vec2::new(1., -1.5));
+    assert_close(closest(shape, Vec2::new(3., 2.)), Vec2::new(3., 2.));
+    // beside, above and past a corner
+    assert_close(closest(shape, Vec2::new(8., 1.)), Vec2::new(3., 1.));
+    assert_close(closest(shape, Vec2::new(-1., 5.)), Vec2::new(-1., 2.));
+    assert_close(closest(shape, Vec2::new(-9., -9.)), Vec2::new(-3., -2.));
+}
+
+#[test]
+fn distance_is_the_metric_to_the_closest_point() {
+    let shape = TargetShape::new(TargetKind::Rect, Vec2::new(6., 4.), 0.);
+    let center = CENTER.extend(0.);
+    let metric = |kind| Metric::new(kind, Vec2::ONE).unwrap();
+
+    // 2 right of and 1 above the top right corner, z is ignored
+    let outside = center + Vec3::new(5., 3., 8.);
+    assert_eq!(
+        shape.distance(&metric(DistanceMetric::SquaredEuclidean), center, outside),
+        5.
+    );
+    assert_eq!(
+        shape.distance(&metric(DistanceMetric::Manhattan), center, outside),
+        3.
+    );
+    let inside = center + Vec3::new(1., 1., 8.);
+    assert_eq!(
+        shape.distance(&metric(DistanceMetric::Chebyshev), center, inside),
+        0.
+    );
+}
@@ -0,0 +1,92 @@
+//! Writes traces of several frames per sample size, reads the positions back and checks
+//! that headers with a bad magic, version or size and files of the wrong length are rejected.
+
+#![cfg(feature = "bevy")]
+
+use bevy::math::Vec2;
+
+use iteration2::trace::*;
+
+mod common;
+use common::TempFile;
+
+fn frame(balls: usize, seed: f32) -> Vec<(Vec2, Vec2)> {
+    (0..=balls)
+        .map(|i| {
+            let i = i as f32 + seed;
+            (Vec2::new(i, -i), Vec2::new(i * 0.5, i * 2.))
+        })
+        .collect()
+}
+
+fn header(samples: &[(u32, u32)]) -> Vec<u8> {
+    let mut bytes = b"BTRC".to_vec();
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend((samples.len() as u32).to_le_bytes());
+    for (balls, frames) in samples {
+        bytes.extend(balls.to_le_bytes());
+        bytes.extend(frames.to_le_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn trace_round_trip() {
+    let mut trace = Trace {
+        samples: vec![SampleTrace::new(2), SampleTrace::new(5)],
+    };
+    for f in 0..3 {
+        trace.samples[0].push_frame(&frame(2, f as f32));
+    }
+    trace.samples[1].push_frame(&frame(5, 10.));
+
+    let file = TempFile::new("round_trip.btrc");
+    trace.write(&file.0).unwrap();
+    let read = Trace::read(&file.0).unwrap();
+
+    assert_eq!(read.samples.len(), 2);
+    assert!(read.covers(&[2, 5]));
+    assert!(!read.covers(&[2, 6]));
+    assert_eq!(read.samples[0].frames(), 3);
+    assert_eq!(read.samples[1].frames(), 1);
+    for f in 0..3 {
+        for (ball, expected) in frame(2, f as f32).into_iter().enumerate() {
+            assert_eq!(read.samples[0].get(f, ball), expected);
+        }
+    }
+    assert_eq!(read.samples[1].get(0, 5), frame(5, 10.)[5]);
+}
+
+#[test]
+fn trace_rejects_broken_files() {
+    let cases: [(&str, Vec<u8>, &str); 5] = [
+        (
+            "magic",
+            b"NOPE\x01\0\0\0\0\0\0\0".to_vec(),
+            "not a trace file",
+        ),
+        (
+            "version",
+            [b"BTRC".as_slice(), &[9, 0, 0, 0, 0, 0, 0, 0]].concat(),
+            "version 9",
+        ),
+        ("truncated", header(&[(2, 3)]), "unexpected end of file"),
+        // (u32::MAX + 1) * 4 * u32::MAX * 4 bytes does not fit in a usize
+        (
+            "overflow",
+            header(&[(u32::MAX, u32::MAX)]),
+            "sample 0 is too large",
+        ),
+        (
+            "trailing",
+            [header(&[(1, 0)]), vec![0; 3]].concat(),
+            "3 bytes after the last sample",
+        ),
+    ];
+
+    for (name, bytes, message) in cases {
+        let file = TempFile::with_bytes(&format!("{name}.btrc"), &bytes);
+        let error = Trace::read(&file.0).unwrap_err();
+        assert!(error.contains(message), "{name}: {error}");
+    }
+}